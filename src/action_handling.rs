@@ -1,27 +1,565 @@
 use std::{
-    collections::{BTreeSet, HashMap},
+    collections::HashMap,
     iter::Cycle,
     path::Path,
     path::PathBuf,
-    process::Command,
+    sync::{
+        mpsc::{channel, Receiver, TryRecvError},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
 
-use vlc::{MarqueeOption, MediaPlayer};
+use vlc::{MarqueeOption, MediaPlayer, MediaPlayerAudioEx};
 
-use crate::{ffmpeg, ClipType, Cutmarks, CLIP_SUFFIX_DEFENSE, CLIP_SUFFIX_OFFENSE};
+use crate::{
+    align::{self, Alignment},
+    ffmpeg::{self, CondensedVideo, CutMode},
+    jobs::{Job, JobId, JobQueue, JobStatus, JobUpdate},
+    media::MediaProcessor,
+    project::{
+        clip_file_name, format_timecode, AfterLoop, Clip, LoopOptions, MediaMetadata,
+        PlayAnnotation, Project, Reel, ReviewClip, UnknownClip,
+    },
+    settings::PlaybackSettings,
+    Cutmarks,
+};
 
 use super::Action;
 
-struct MediaMetadata {
-    clips: BTreeSet<i64>,
-    cutmarks: Option<Box<Cutmarks>>,
+/// How often the other angles are compared with the current video, see `sync_angles`
+const ANGLE_SYNC_INTERVAL: Duration = Duration::from_millis(500);
+/// Angles which are further away from the current video are moved back in sync
+const MAX_ANGLE_DRIFT_MS: i64 = 200;
+/// How far `NudgeAngleForward` and `NudgeAngleBack` move an angle, about one frame
+const ANGLE_NUDGE_MS: i64 = 40;
+/// VLC still reports the time before a seek for a moment, so the end of a loop is not checked
+/// again for this long after jumping back to its start
+const LOOP_RESTART_GRACE: Duration = Duration::from_millis(300);
+/// How often the video is moved on while scrubbing or playing in reverse, see `Action::Shuttle`
+/// and `Action::PlayReverse`
+const SHUTTLE_INTERVAL: Duration = Duration::from_millis(40);
+
+/// How the angles of the current video are shown
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum AngleLayout {
+    /// Only the active angle is shown
+    Single,
+    /// All angles are shown next to each other
+    SideBySide,
+}
+
+/// What the video windows show, see `FltkGui::show_angles`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct AngleView {
+    /// Number of angles which are played, including the current video
+    pub(crate) count: usize,
+    /// The angle which is heard and shown in `AngleLayout::Single`. 0 is the current video.
+    pub(crate) active: usize,
+    pub(crate) layout: AngleLayout,
+}
+
+/// Another angle of the current video, played in sync with it
+struct SyncedAngle {
+    media_path: PathBuf,
+    /// A time `t` of the current video corresponds to `t + offset_ms` of this angle
+    offset_ms: i64,
+}
+
+/// Scrubbing of the current video by seeking while a trigger is held, see `Action::Shuttle`,
+/// or playing it in reverse, which VLC cannot do
+struct Shuttle {
+    /// Multiple of the normal speed, negative when scrubbing backwards
+    speed: f32,
+    /// Time of the current video that is shown
+    position: i64,
+    last_step: Instant,
+    /// Whether the video was playing before and is played again when scrubbing stops
+    resume: bool,
+    /// Playing in reverse with `Action::PlayReverse`, which loops backwards through the loop
+    reverse: bool,
+}
+
+impl Shuttle {
+    /// Moves the position on by `elapsed_ms` of playing at `speed` and returns it.
+    /// Within the video of length `duration`, playing in reverse jumps from the start of
+    /// `loop_range` back to its end.
+    fn advance(
+        &mut self,
+        elapsed_ms: f32,
+        duration: Option<i64>,
+        loop_range: Option<(i64, i64)>,
+    ) -> i64 {
+        let mut position = self.position + (elapsed_ms * self.speed) as i64;
+        if let Some(duration) = duration {
+            position = position.min(duration);
+        }
+        if let Some((loop_start, loop_end)) = loop_range {
+            if self.reverse && position < loop_start {
+                position = loop_end;
+            }
+        }
+        self.position = position.max(0);
+        self.position
+    }
+}
+
+/// What to do at the end of a loop, see `LoopProgress::loop_ended`
+#[derive(Debug, PartialEq)]
+enum LoopStep {
+    /// Plays the loop again at `rate` after `pause`
+    Repeat { rate: f32, pause: Option<Duration> },
+    /// All repetitions have been played, the loop is left as set in the options at `rate`
+    Finish { rate: f32, after: AfterLoop },
 }
 
-impl MediaMetadata {
-    fn new() -> MediaMetadata {
-        MediaMetadata {
-            clips: BTreeSet::new(),
-            cutmarks: None,
+/// How often the current loop has been played, see `LoopOptions`
+struct LoopProgress {
+    /// `loop_start` and `loop_end` of the loop, the progress starts over when they change
+    range: (i64, i64),
+    /// Number of repetitions that have been played
+    played: u32,
+    /// The speed of the repetitions that are not played in slow motion
+    rate: f32,
+    /// When the loop was last jumped back to its start
+    restarted: Option<Instant>,
+    /// End of the pause before the next repetition
+    resume_at: Option<Instant>,
+    /// All repetitions have been played, the loop is played again once the video is back in it
+    finished: bool,
+}
+
+impl LoopProgress {
+    fn new(range: (i64, i64), rate: f32) -> LoopProgress {
+        LoopProgress {
+            range,
+            played: 0,
+            rate,
+            restarted: None,
+            resume_at: None,
+            finished: false,
+        }
+    }
+
+    /// Counts the repetition that ended at `rate` and decides how to go on
+    fn loop_ended(&mut self, options: &LoopOptions, rate: f32) -> LoopStep {
+        // the user may have changed the speed during a repetition at normal speed
+        let ended_in_slow_motion = options.slow_motion_rate.is_some() && self.played % 2 == 1;
+        if !ended_in_slow_motion {
+            self.rate = rate;
+        }
+        self.played += 1;
+        self.restarted = Some(Instant::now());
+        if options.repeat != 0 && self.played >= options.repeat {
+            self.played = 0;
+            self.finished = true;
+            return LoopStep::Finish {
+                rate: self.rate,
+                after: options.after,
+            };
+        }
+        let rate = match options.slow_motion_rate {
+            Some(slow_motion_rate) if self.played % 2 == 1 => slow_motion_rate,
+            _ => self.rate,
+        };
+        let pause = if options.pause_seconds > 0.0 {
+            Some(Duration::from_secs_f64(options.pause_seconds))
+        } else {
+            None
+        };
+        LoopStep::Repeat { rate, pause }
+    }
+}
+
+/// Clips played one after the other from their videos, see `Action::ReviewClips`
+struct Review {
+    clips: Vec<ReviewClip>,
+    /// Index of the clip that is played
+    current: usize,
+    /// The video of the current clip was opened and is seeked to the clip once it is playing
+    seek_pending: bool,
+    /// When the current clip was seeked to
+    started: Instant,
+}
+
+/// A clip whose cut job has not finished yet
+struct PendingClip {
+    /// The project the clip belongs to, which may not be open anymore when the cut is finished
+    project_dir: PathBuf,
+    media_path: PathBuf,
+    start: i64,
+    clip: Clip,
+}
+
+impl PendingClip {
+    /// Adds the clip to `project`, or to the file of its own project if another one is open.
+    /// Returns whether `project` changed.
+    fn add_to(self, project: &mut Project) -> bool {
+        let PendingClip {
+            project_dir,
+            media_path,
+            start,
+            clip,
+        } = self;
+        if project.dir() == project_dir {
+            if let Some(metadata) = project.media_metadata_mut(&media_path) {
+                metadata.clips.insert(start, clip);
+            }
+            return true;
+        }
+        let result = update_project_file(&project_dir, |own_project| {
+            if let Some(metadata) = own_project.media_metadata_mut(&media_path) {
+                metadata.clips.insert(start, clip);
+            }
+        });
+        if let Err(e) = result {
+            println!(
+                "could not add the clip to the project in {:?}: {}",
+                project_dir, e
+            );
+        }
+        false
+    }
+}
+
+/// Changes the project file in `project_dir` of a project which is not open anymore
+fn update_project_file(
+    project_dir: &Path,
+    update: impl FnOnce(&mut Project),
+) -> Result<(), std::io::Error> {
+    let mut project = Project::load(project_dir)?;
+    update(&mut project);
+    project.save()
+}
+
+/// Jobs which concatenate the clips of a project, or why they could not be created
+type PreparedConcat = Result<Vec<(Job, CondensedVideo)>, String>;
+
+/// The job which renders a reel, or why it could not be created
+type PreparedReel = Result<(Job, CondensedVideo), String>;
+
+/// Clip files found by `Project::unknown_clips` with their durations in ms, or why they could not
+/// be probed
+type ProbedClips = Vec<(UnknownClip, Result<i64, String>)>;
+
+/// Offsets of the other angles of a video computed from their audio, or why they could not be
+type AngleAlignments = Result<Vec<Alignment>, String>;
+
+/// The background jobs of the `ActionHandler` and the clips they add to the project
+struct MediaJobs {
+    processor: Arc<dyn MediaProcessor>,
+    queue: JobQueue,
+    pending_clips: HashMap<JobId, PendingClip>,
+    /// Videos written by running concatenation jobs
+    condensed_videos: HashMap<JobId, CondensedVideo>,
+    /// Receives the concatenation jobs while the clips are probed, see `concat_clips`
+    preparing_concat: Option<Receiver<PreparedConcat>>,
+    /// Receive the jobs which render reels while the clips are probed, with the names of the
+    /// reels, see `render_reel`
+    preparing_reels: Vec<(String, Receiver<PreparedReel>)>,
+    /// Receives the durations of the clip files found when opening a project, see `probe_clips`
+    recovering_clips: Option<Receiver<ProbedClips>>,
+}
+
+impl MediaJobs {
+    fn new(processor: Arc<dyn MediaProcessor>) -> MediaJobs {
+        MediaJobs {
+            queue: JobQueue::new(processor.clone()),
+            processor,
+            pending_clips: HashMap::new(),
+            condensed_videos: HashMap::new(),
+            preparing_concat: None,
+            preparing_reels: Vec::new(),
+            recovering_clips: None,
+        }
+    }
+
+    /// Files of the clips of `project` which are still being cut
+    fn pending_files(&self, project: &Project) -> Vec<PathBuf> {
+        self.pending_clips
+            .values()
+            .filter(|pending| pending.project_dir == project.dir())
+            .map(|pending| {
+                project.clip_file_path(&pending.media_path, pending.start, &pending.clip)
+            })
+            .collect()
+    }
+
+    /// Starts cutting `start` to `end` of `media_path` into the clips directory of the project.
+    /// The clip is added to the project when the job succeeded, see `finish`.
+    fn cut_clip(
+        &mut self,
+        project: &Project,
+        media_path: &Path,
+        start: i64,
+        end: i64,
+        tag_names: Vec<String>,
+    ) -> Result<JobId, String> {
+        let tags = project.find_tags(&tag_names)?;
+        let clips_dir_path = project.clips_dir();
+        std::fs::create_dir_all(&clips_dir_path)
+            .map_err(|e| format!("could not create {:?}: {}", clips_dir_path, e))?;
+
+        let out_file_path = clips_dir_path.join(clip_file_name(media_path, start, &tags));
+        let mut description = format!(
+            "cut {} at {}",
+            media_path.file_name().unwrap_or_default().to_string_lossy(),
+            format_timecode(start)
+        );
+        if !tag_names.is_empty() {
+            description += &format!(" as {}", tag_names.join("+"));
+        }
+        let job = self.processor.cut(
+            description,
+            media_path,
+            start,
+            end,
+            &out_file_path,
+            project.cut_settings(),
+        );
+        let job_id = self.queue.submit(job);
+        self.pending_clips.insert(
+            job_id,
+            PendingClip {
+                project_dir: project.dir().to_path_buf(),
+                media_path: media_path.to_path_buf(),
+                start,
+                clip: Clip {
+                    end: Some(end),
+                    tags: tag_names,
+                    annotation: PlayAnnotation::default(),
+                },
+            },
+        );
+        Ok(job_id)
+    }
+
+    /// Starts writing the animation and the contact sheet of `start` to `end` of `media_path`
+    /// into the previews directory of the project. Returns the number of submitted jobs.
+    fn export_preview(
+        &mut self,
+        project: &Project,
+        media_path: &Path,
+        start: i64,
+        end: i64,
+        output_file_stem: &str,
+    ) -> Result<usize, String> {
+        let previews_dir = project.previews_dir();
+        std::fs::create_dir_all(&previews_dir)
+            .map_err(|e| format!("could not create {:?}: {}", previews_dir, e))?;
+        let jobs = ffmpeg::preview_jobs(
+            self.processor.as_ref(),
+            media_path,
+            start,
+            end,
+            project.preview_settings(),
+            &previews_dir,
+            output_file_stem,
+        );
+        let count = jobs.len();
+        for job in jobs {
+            self.queue.submit(job);
+        }
+        Ok(count)
+    }
+
+    /// Starts rendering a reel of the project into the reels directory
+    fn render_reel(&mut self, project: &Project, reel: &Reel) -> Result<(), String> {
+        let clip_files = project.reel_clip_files(reel)?;
+        let reels_dir = project.reels_dir();
+        std::fs::create_dir_all(&reels_dir)
+            .map_err(|e| format!("could not create {:?}: {}", reels_dir, e))?;
+
+        let (tx, rx) = channel();
+        let processor = self.processor.clone();
+        let description = format!("render reel \"{}\"", reel.name);
+        let file_stem = reel.file_stem();
+        let options = reel.options.clone();
+        let texts = project.reel_clip_texts(reel);
+        let encoder = project.cut_settings().encoder.clone();
+        std::thread::spawn(move || {
+            let prepared = ffmpeg::concat_job(
+                processor.as_ref(),
+                description,
+                &clip_files,
+                &reels_dir,
+                &file_stem,
+                &encoder,
+                Some(ffmpeg::Decorations {
+                    options: &options,
+                    texts: &texts,
+                }),
+            );
+            // the receiver is gone if VAC exits in the meantime
+            let _ = tx.send(prepared);
+        });
+        self.preparing_reels.push((reel.name.clone(), rx));
+        Ok(())
+    }
+
+    /// Probes clip files in the background. The durations of the clip files of a project which
+    /// was opened before are dropped.
+    fn probe_clips(&mut self, unknown_clips: Vec<UnknownClip>) {
+        let (tx, rx) = channel();
+        let processor = self.processor.clone();
+        std::thread::spawn(move || {
+            let probed_clips: ProbedClips = unknown_clips
+                .into_iter()
+                .map(|clip| {
+                    let duration = processor.probe(&clip.file).map(|format| format.duration_ms);
+                    (clip, duration)
+                })
+                .collect();
+            // the receiver is gone if another project was opened in the meantime
+            let _ = tx.send(probed_clips);
+        });
+        self.recovering_clips = Some(rx);
+    }
+
+    /// The clip files started by `probe_clips` once they are all probed
+    fn probed_clips(&mut self) -> Option<ProbedClips> {
+        let probed_clips = match self.recovering_clips.as_ref()?.try_recv() {
+            Ok(probed_clips) => probed_clips,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Vec::new(),
+        };
+        self.recovering_clips = None;
+        Some(probed_clips)
+    }
+
+    /// Submits the jobs of the reels whose clips were probed since the last call and returns
+    /// the names of these reels with their videos
+    fn submit_prepared_reels(&mut self) -> Vec<(String, Result<CondensedVideo, String>)> {
+        let mut submitted = Vec::new();
+        let queue = &mut self.queue;
+        self.preparing_reels.retain(|(name, rx)| {
+            let prepared = match rx.try_recv() {
+                Ok(prepared) => prepared,
+                Err(TryRecvError::Empty) => return true,
+                Err(TryRecvError::Disconnected) => Err("preparing the reel failed".to_owned()),
+            };
+            let reel_video = prepared.map(|(job, reel_video)| {
+                queue.submit(job);
+                reel_video
+            });
+            submitted.push((name.clone(), reel_video));
+            false
+        });
+        submitted
+    }
+
+    /// Starts probing the clips of the project in a background thread. The jobs which
+    /// concatenate them into one video per tag are submitted by `submit_prepared_concat`.
+    fn concat_clips(&mut self, project: &Project) -> Result<(), String> {
+        if self.preparing_concat.is_some() {
+            return Err("the clips are already being prepared for concatenation".to_owned());
+        }
+        let clips_dir_path = project.clips_dir();
+        let condensed_dir_path = project.dir().join("_condensed");
+        for dir_path in [&clips_dir_path, &condensed_dir_path].iter() {
+            std::fs::create_dir_all(dir_path)
+                .map_err(|e| format!("could not create {:?}: {}", dir_path, e))?;
+        }
+
+        let (tx, rx) = channel();
+        let processor = self.processor.clone();
+        let tags = project.tags().to_vec();
+        let encoder = project.cut_settings().encoder.clone();
+        std::thread::spawn(move || {
+            let prepared = ffmpeg::concat_jobs(
+                processor.as_ref(),
+                &clips_dir_path,
+                &condensed_dir_path,
+                &tags,
+                &encoder,
+            );
+            // the receiver is gone if VAC exits in the meantime
+            let _ = tx.send(prepared);
+        });
+        self.preparing_concat = Some(rx);
+        Ok(())
+    }
+
+    /// Submits the concatenation jobs once the clips are probed and returns their number or
+    /// why they could not be created. Returns `None` while the clips are still being probed.
+    fn submit_prepared_concat(&mut self) -> Option<Result<usize, String>> {
+        let prepared = match self.preparing_concat.as_ref()?.try_recv() {
+            Ok(prepared) => prepared,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err("preparing the concatenation failed".to_owned()),
+        };
+        self.preparing_concat = None;
+        Some(prepared.map(|jobs| {
+            let count = jobs.len();
+            for (job, condensed_video) in jobs {
+                let job_id = self.queue.submit(job);
+                self.condensed_videos.insert(job_id, condensed_video);
+            }
+            count
+        }))
+    }
+
+    /// Adds the clip of a succeeded cut to the project it was cut for and forgets the clips of
+    /// failed or cancelled cuts. Returns whether `project` changed.
+    fn finish(&mut self, update: &JobUpdate, project: &mut Project) -> bool {
+        if update.status.is_finished() {
+            if let Some(video) = self.condensed_videos.remove(&update.id) {
+                if update.status == JobStatus::Succeeded {
+                    println!(
+                        "{:?}: {} clips, {}",
+                        video.path, video.clip_count, video.strategy
+                    );
+                }
+            }
+        }
+        match &update.status {
+            JobStatus::Succeeded => match self.pending_clips.remove(&update.id) {
+                Some(pending) => pending.add_to(project),
+                None => false,
+            },
+            JobStatus::Failed(_) | JobStatus::Cancelled => {
+                self.pending_clips.remove(&update.id);
+                false
+            }
+            JobStatus::Queued | JobStatus::Running(_) => false,
+        }
+    }
+}
+
+/// The loop set with `StartLoop` and `EndLoop`, unless one of them is missing or the end is not
+/// after the start
+fn loop_range(loop_start: i64, loop_end: i64) -> Option<(i64, i64)> {
+    if loop_start >= 0 && loop_end > loop_start {
+        Some((loop_start, loop_end))
+    } else {
+        None
+    }
+}
+
+/// Advances `media_iter` past `media_path`, as if the videos before it had been skipped with
+/// `NextMedia`. Returns false and leaves `media_iter` unchanged if `media_path` is not among its
+/// videos, e.g. because it is another angle.
+fn advance_media_iter(
+    media_iter: &mut Cycle<std::vec::IntoIter<PathBuf>>,
+    media_path: &Path,
+) -> bool {
+    let mut advanced = media_iter.clone();
+    let first = match advanced.next() {
+        Some(first) => first,
+        None => return false,
+    };
+    let mut media = first.clone();
+    loop {
+        if media == media_path {
+            *media_iter = advanced;
+            return true;
+        }
+        media = match advanced.next() {
+            Some(media) => media,
+            None => return false,
+        };
+        if media == first {
+            return false;
         }
     }
 }
@@ -29,11 +567,26 @@ impl MediaMetadata {
 pub(super) struct ActionHandler<'vlc> {
     vlc_instance: &'vlc vlc::Instance,
     mdp: MediaPlayer,
+    /// Players for the other angles of the current video, each one shows in its own window
+    angle_mdps: Vec<MediaPlayer>,
+    /// The other angles of the current video. The first ones are played by `angle_mdps`,
+    /// the remaining ones are only cut.
+    angles: Vec<SyncedAngle>,
+    active_angle: usize,
+    angle_layout: AngleLayout,
+    last_angle_sync: Instant,
+    /// The video whose angles are aligned by their audio and receives the offsets of the
+    /// other angles, see `align_angles`
+    aligning: Option<(PathBuf, Receiver<AngleAlignments>)>,
+    shuttle: Option<Shuttle>,
+    loop_progress: Option<LoopProgress>,
+    review: Option<Review>,
     marquee_option: MarqueeOption,
-    project_dir: PathBuf,
     media_iter: Cycle<std::vec::IntoIter<PathBuf>>,
     current_media_path: Option<PathBuf>,
-    media_metadata: HashMap<PathBuf, MediaMetadata>,
+    project: Project,
+    jobs: MediaJobs,
+    playback: PlaybackSettings,
     loop_start: i64,
     loop_end: i64,
 }
@@ -42,8 +595,12 @@ impl<'vlc> ActionHandler<'vlc> {
     pub(super) fn new(
         vlc_instance: &'vlc vlc::Instance,
         mdp: MediaPlayer,
-        project_dir: PathBuf,
-    ) -> Result<ActionHandler<'vlc>, std::io::Error> {
+        angle_mdps: Vec<MediaPlayer>,
+        project: Project,
+        media_paths: Vec<PathBuf>,
+        processor: Arc<dyn MediaProcessor>,
+        playback: PlaybackSettings,
+    ) -> ActionHandler<'vlc> {
         // Initialize VLC Marquee -- maybe we don't need this anymore with FLTK
         let marquee_option = MarqueeOption {
             position: Some(0),
@@ -52,97 +609,710 @@ impl<'vlc> ActionHandler<'vlc> {
             ..Default::default()
         };
 
-        let mut media_paths: Vec<PathBuf> = project_dir
-            .read_dir()?
-            .flatten()
-            .filter(|entry| {
-                if let Some(s) = entry.path().extension() {
-                    if let Some(extension) = s.to_str() {
-                        if super::VIDEO_EXTENSIONS.contains(&extension.to_uppercase().as_str()) {
-                            return true;
-                        }
-                    }
-                }
-                false
-            })
-            .map(|entry| entry.path())
-            .collect();
-
-        media_paths.sort();
         let media_iter = media_paths.into_iter().cycle();
         let mut ah = ActionHandler {
             vlc_instance,
             mdp,
+            angle_mdps,
+            angles: Vec::new(),
+            active_angle: 0,
+            angle_layout: AngleLayout::Single,
+            last_angle_sync: Instant::now(),
+            aligning: None,
+            shuttle: None,
+            loop_progress: None,
+            review: None,
             marquee_option,
-            project_dir,
             media_iter,
             current_media_path: None,
-            media_metadata: HashMap::new(),
+            project,
+            jobs: MediaJobs::new(processor),
+            playback,
             loop_start: -1,
             loop_end: -1,
         };
         let next_media = ah.media_iter.next().unwrap();
         ah.play_media(&next_media);
+        ah.recover_clips();
 
-        Ok(ah)
+        ah
     }
 
+    /// Loads the project in `dir_path` and lists the videos which are opened by `NextMedia`
+    pub(super) fn load_project(dir_path: &Path) -> Result<(Project, Vec<PathBuf>), std::io::Error> {
+        let project = Project::load(dir_path)?;
+        let mut media_paths = super::list_media(dir_path)?;
+        media_paths.retain(|path| !project.is_following_angle(path));
+        if media_paths.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("no videos in {:?}", dir_path),
+            ));
+        }
+        Ok((project, media_paths))
+    }
+
+    /// Opens another project. If it cannot be loaded, the current one stays open.
     pub(super) fn set_project_directory(
         &mut self,
         dir_path: PathBuf,
     ) -> Result<(), std::io::Error> {
-        let mut media_paths: Vec<PathBuf> = dir_path
-            .read_dir()?
-            .flatten()
-            .filter(|entry| {
-                if let Some(s) = entry.path().extension() {
-                    if let Some(extension) = s.to_str() {
-                        if super::VIDEO_EXTENSIONS.contains(&extension.to_uppercase().as_str()) {
-                            return true;
-                        }
-                    }
-                }
-                false
-            })
-            .map(|entry| entry.path())
-            .collect();
-
-        media_paths.sort();
-        let media_iter = media_paths.into_iter().cycle();
-        self.project_dir = dir_path;
-        self.media_metadata = HashMap::new();
-        self.media_iter = media_iter;
+        let (project, media_paths) = ActionHandler::load_project(&dir_path)?;
+        self.project = project;
+        self.media_iter = media_paths.into_iter().cycle();
+        // the review, the loop and the alignment refer to videos of the previous project
+        self.aligning = None;
+        self.review = None;
+        self.loop_progress = None;
+        self.loop_start = -1;
+        self.loop_end = -1;
         let next_media = self.media_iter.next().unwrap();
         self.play_media(&next_media);
+        self.recover_clips();
 
         Ok(())
     }
 
+    /// Adds clips that were cut before but are missing in the project file. The clip files are
+    /// probed in the background, see `add_recovered_clips`.
+    fn recover_clips(&mut self) {
+        // clips which are still being cut are added when their job is finished
+        let pending_files = self.jobs.pending_files(&self.project);
+        let (unknown_clips, problems) = self.project.unknown_clips(&pending_files);
+        self.report_recovery(0, &problems);
+        self.jobs.recovering_clips = None;
+        if !unknown_clips.is_empty() {
+            self.jobs.probe_clips(unknown_clips);
+        }
+    }
+
+    /// Adds the clips found by `recover_clips` once their files are probed
+    fn add_recovered_clips(&mut self) {
+        if let Some(probed_clips) = self.jobs.probed_clips() {
+            let pending_files = self.jobs.pending_files(&self.project);
+            let (changed, problems) = self
+                .project
+                .add_recovered_clips(&pending_files, probed_clips);
+            self.report_recovery(changed, &problems);
+        }
+    }
+
+    fn report_recovery(&mut self, changed: usize, problems: &[String]) {
+        for problem in problems.iter() {
+            println!("could not recover clip {}", problem);
+        }
+        if changed > 0 {
+            println!("recovered {} clips from the clips directory", changed);
+            self.save_project();
+        }
+        if !problems.is_empty() {
+            let msg = format!("{} clip files could not be recovered", problems.len());
+            self.show_marquee(&msg);
+        }
+    }
+
     fn get_current_media_metadata_mut(&mut self) -> Option<&mut MediaMetadata> {
         let path = self.current_media_path.as_ref()?;
-        self.media_metadata.get_mut(path)
+        self.project.media_metadata_mut(path)
     }
 
     fn get_current_media_metadata(&self) -> Option<&MediaMetadata> {
         let path = self.current_media_path.as_ref()?;
-        self.media_metadata.get(path)
+        self.project.media_metadata(path)
     }
 
     fn play_media(&mut self, current_media_path: &Path) {
         let md = vlc::Media::new_path(self.vlc_instance, &current_media_path).unwrap();
         self.current_media_path = Some(current_media_path.to_path_buf());
+        self.shuttle = None;
         self.mdp.set_media(&md);
-        if !self.media_metadata.contains_key(current_media_path) {
-            let result = self
-                .media_metadata
-                .insert(current_media_path.to_path_buf(), MediaMetadata::new());
-            assert!(result.is_none(), "Media Metadata already inserted?")
+        assert!(
+            self.get_current_media_metadata_mut().is_some(),
+            "Media Metadata could not be created"
+        );
+        self.mdp.play().unwrap();
+        self.load_angles(current_media_path);
+    }
+
+    /// Starts the other angles of the video in the angle players
+    fn load_angles(&mut self, media_path: &Path) {
+        for mdp in self.angle_mdps.iter() {
+            mdp.stop();
+        }
+        self.angles = self
+            .project
+            .other_angles(media_path)
+            .into_iter()
+            .map(|(media_path, offset_ms)| SyncedAngle {
+                media_path,
+                offset_ms,
+            })
+            .collect();
+        if self.angles.len() > self.angle_mdps.len() {
+            println!(
+                "only {} of {} other angles can be shown, but all of them are cut",
+                self.angle_mdps.len(),
+                self.angles.len()
+            );
         }
+        for (mdp, angle) in self.angle_players() {
+            let md = vlc::Media::new_path(self.vlc_instance, &angle.media_path).unwrap();
+            mdp.set_media(&md);
+            mdp.play().unwrap();
+        }
+        self.active_angle = 0;
+        self.mute_inactive_angles();
+        // the players need a moment to start before they can be synced
+        self.last_angle_sync = Instant::now();
+    }
+
+    /// The players of the other angles with the angles they play
+    fn angle_players(&self) -> impl Iterator<Item = (&MediaPlayer, &SyncedAngle)> {
+        self.angle_mdps.iter().zip(self.angles.iter())
+    }
+
+    /// Only the active angle is heard
+    fn mute_inactive_angles(&self) {
+        self.mdp.set_mute(self.active_angle != 0);
+        for (i, (mdp, _)) in self.angle_players().enumerate() {
+            mdp.set_mute(self.active_angle != i + 1);
+        }
+    }
+
+    /// Jumps to `time` of the current video and to the same moment of the other angles
+    fn seek(&self, time: i64) {
+        self.mdp.set_time(time);
+        for (mdp, angle) in self.angle_players() {
+            mdp.set_time((time + angle.offset_ms).max(0));
+        }
+    }
+
+    fn play(&self) {
         self.mdp.play().unwrap();
+        for (mdp, _) in self.angle_players() {
+            mdp.play().unwrap();
+        }
+    }
+
+    fn pause(&self) {
+        self.mdp.set_pause(true);
+        for (mdp, _) in self.angle_players() {
+            mdp.set_pause(true);
+        }
+    }
+
+    fn set_rate(&mut self, rate: f32) {
+        if self.mdp.set_rate(rate).is_err() {
+            println!("could not set the speed to {}", rate);
+            return;
+        }
+        if let Some(shuttle) = &mut self.shuttle {
+            if shuttle.reverse {
+                shuttle.speed = -rate;
+            }
+        }
+        for (mdp, _) in self.angle_players() {
+            let _ = mdp.set_rate(rate);
+        }
+        self.show_marquee(&format!("speed {}x", rate));
+    }
+
+    /// Playback rate of the current video, 1 is normal speed
+    pub(super) fn get_rate(&self) -> f32 {
+        self.mdp.get_rate()
+    }
+
+    /// Starts, changes or stops scrubbing at `speed`, see `Action::Shuttle`
+    fn set_shuttle_speed(&mut self, speed: f32) {
+        if speed == 0.0 {
+            if let Some(shuttle) = self.shuttle.take() {
+                if shuttle.resume {
+                    self.play();
+                }
+            }
+            return;
+        }
+        match &mut self.shuttle {
+            Some(shuttle) => {
+                // scrubbing takes over from playing in reverse and stops it
+                shuttle.speed = speed;
+                shuttle.reverse = false;
+            }
+            None => {
+                let position = match self.mdp.get_time() {
+                    Some(time) => time,
+                    None => return,
+                };
+                let resume = self.mdp.is_playing();
+                self.pause();
+                self.shuttle = Some(Shuttle {
+                    speed,
+                    position,
+                    last_step: Instant::now(),
+                    resume,
+                    reverse: false,
+                });
+            }
+        }
+    }
+
+    /// The loop as start and end, if the end of the loop is set
+    fn loop_range(&self) -> Option<(i64, i64)> {
+        if self.loop_end == -1 {
+            None
+        } else {
+            Some((self.loop_start.max(0), self.loop_end))
+        }
+    }
+
+    /// Starts playing the current video backwards at the current speed, see `Action::PlayReverse`.
+    /// In a loop, the video is played from the end of the loop.
+    fn play_reverse(&mut self) {
+        let mut position = match self.mdp.get_time() {
+            Some(time) => time,
+            None => return,
+        };
+        if let Some((loop_start, loop_end)) = self.loop_range() {
+            if position < loop_start || position > loop_end {
+                position = loop_end;
+            }
+        }
+        self.pause();
+        self.shuttle = Some(Shuttle {
+            speed: -self.mdp.get_rate(),
+            position,
+            last_step: Instant::now(),
+            resume: false,
+            reverse: true,
+        });
+        self.show_marquee("reverse");
+    }
+
+    fn is_playing_reverse(&self) -> bool {
+        match &self.shuttle {
+            Some(shuttle) => shuttle.reverse,
+            None => false,
+        }
+    }
+
+    /// Moves the video on while scrubbing or playing in reverse. Seeking is slow, so this is
+    /// done every `SHUTTLE_INTERVAL` by the time that has passed.
+    /// Playing in reverse stops at the start of the video.
+    pub(super) fn shuttle(&mut self) {
+        let duration = self.mdp.get_media().and_then(|md| md.duration());
+        let loop_range = self.loop_range();
+        let (position, reverse) = match &mut self.shuttle {
+            Some(shuttle) if shuttle.last_step.elapsed() >= SHUTTLE_INTERVAL => {
+                let elapsed_ms = shuttle.last_step.elapsed().as_millis() as f32;
+                shuttle.last_step = Instant::now();
+                (
+                    shuttle.advance(elapsed_ms, duration, loop_range),
+                    shuttle.reverse,
+                )
+            }
+            _ => return,
+        };
+        self.seek(position);
+        if reverse && position == 0 {
+            self.shuttle = None;
+        }
+    }
+
+    /// Starts playing the clips with the given tags one after the other, see `Action::ReviewClips`
+    fn start_review(&mut self, tag_names: &[String]) {
+        if let Err(e) = self.project.find_tags(tag_names) {
+            println!("cannot review clips: {}", e);
+            self.show_marquee(&e);
+            return;
+        }
+        let clips = self.project.review_clips(tag_names);
+        if clips.is_empty() {
+            self.show_marquee("no clips to review");
+            return;
+        }
+        self.loop_end = -1;
+        self.review = Some(Review {
+            clips,
+            current: 0,
+            seek_pending: false,
+            started: Instant::now(),
+        });
+        self.play_review_clip(0);
+    }
+
+    /// Plays the clip of the review at `index`, opening its video if needed
+    fn play_review_clip(&mut self, index: usize) {
+        let (clip, count) = match &self.review {
+            Some(review) => (review.clips[index].clone(), review.clips.len()),
+            None => return,
+        };
+        let seek_pending = self.current_media_path.as_ref() != Some(&clip.media_path);
+        if seek_pending {
+            // `NextMedia` continues after the reviewed video
+            advance_media_iter(&mut self.media_iter, &clip.media_path);
+            self.play_media(&clip.media_path);
+        } else {
+            self.seek(clip.start);
+            self.play();
+        }
+        if let Some(review) = &mut self.review {
+            review.current = index;
+            review.seek_pending = seek_pending;
+            review.started = Instant::now();
+        }
+        self.show_marquee(&format!("clip {} / {}", index + 1, count));
+    }
+
+    /// Moves to the start of the clip of the review after the end of the current one. The review
+    /// ends after the last clip.
+    pub(super) fn check_review(&mut self) {
+        if self.shuttle.is_some() {
+            return;
+        }
+        let review = match &mut self.review {
+            Some(review) => review,
+            None => return,
+        };
+        let clip = &review.clips[review.current];
+        if review.seek_pending {
+            // VLC cannot seek in a video before it is playing
+            if self.mdp.is_playing() {
+                review.seek_pending = false;
+                review.started = Instant::now();
+                let start = clip.start;
+                self.seek(start);
+            }
+            return;
+        }
+        if review.started.elapsed() < LOOP_RESTART_GRACE {
+            return;
+        }
+        match self.mdp.get_time() {
+            Some(time) if time >= clip.end => {}
+            _ => return,
+        }
+        let next = review.current + 1;
+        if next < review.clips.len() {
+            self.play_review_clip(next);
+        } else {
+            self.review = None;
+            self.pause();
+            self.show_marquee("review finished");
+        }
+    }
+
+    /// The index of the reviewed clip and the number of clips, while reviewing clips
+    pub(super) fn review_position(&self) -> Option<(usize, usize)> {
+        self.review
+            .as_ref()
+            .map(|review| (review.current, review.clips.len()))
+    }
+
+    /// Keeps the other angles in sync with the current video. VLC plays every video at its own
+    /// pace, so an angle that drifts away further than `MAX_ANGLE_DRIFT_MS` is moved back.
+    /// An angle that starts later than the current video waits at its start.
+    pub(super) fn sync_angles(&mut self) {
+        if self.angles.is_empty() || self.last_angle_sync.elapsed() < ANGLE_SYNC_INTERVAL {
+            return;
+        }
+        self.last_angle_sync = Instant::now();
+        let time = match self.mdp.get_time() {
+            Some(time) => time,
+            None => return,
+        };
+        let playing = self.mdp.is_playing();
+        let rate = self.mdp.get_rate();
+        for (mdp, angle) in self.angle_players() {
+            let angle_time = time + angle.offset_ms;
+            let angle_playing = playing && angle_time >= 0;
+            if mdp.is_playing() != angle_playing {
+                if angle_playing {
+                    mdp.play().unwrap();
+                } else {
+                    mdp.set_pause(true);
+                }
+            }
+            if (mdp.get_rate() - rate).abs() > f32::EPSILON {
+                let _ = mdp.set_rate(rate);
+            }
+            if let Some(actual_time) = mdp.get_time() {
+                if (actual_time - angle_time.max(0)).abs() > MAX_ANGLE_DRIFT_MS {
+                    mdp.set_time(angle_time.max(0));
+                }
+            }
+        }
+        self.mute_inactive_angles();
+    }
+
+    /// Starts computing the offsets of the other angles of the current video from their audio
+    /// in a background thread. They are stored by `apply_alignments`.
+    fn align_angles(&mut self) -> Result<(), String> {
+        if self.aligning.is_some() {
+            return Err("the angles are already being aligned".to_owned());
+        }
+        let current_media_path = self.current_media_path.clone().unwrap();
+        if self.angles.is_empty() {
+            return Err("the video has no other angles".to_owned());
+        }
+        let media_paths: Vec<PathBuf> = std::iter::once(current_media_path.clone())
+            .chain(self.angles.iter().map(|angle| angle.media_path.clone()))
+            .collect();
+
+        let (tx, rx) = channel();
+        let processor = self.jobs.processor.clone();
+        let work_dir = self.project.work_dir();
+        std::thread::spawn(move || {
+            // the receiver is gone if VAC exits in the meantime
+            let _ = tx.send(align::align_angles(processor, &media_paths, &work_dir));
+        });
+        self.aligning = Some((current_media_path, rx));
+        Ok(())
+    }
+
+    /// Stores the offsets computed by `align_angles` once they are ready
+    fn apply_alignments(&mut self) {
+        let result = match self.aligning.as_ref().map(|(_, rx)| rx.try_recv()) {
+            Some(Ok(result)) => result,
+            Some(Err(TryRecvError::Disconnected)) => Err("aligning the angles failed".to_owned()),
+            Some(Err(TryRecvError::Empty)) | None => return,
+        };
+        let (reference_path, _) = self.aligning.take().unwrap();
+        let alignments = match result {
+            Ok(alignments) => alignments,
+            Err(e) => {
+                println!("cannot align angles: {}", e);
+                self.show_marquee("error aligning angles");
+                return;
+            }
+        };
+
+        for alignment in alignments.iter() {
+            println!("{}", alignment);
+        }
+        if let Err(e) = align::store_offsets(&mut self.project, &reference_path, &alignments) {
+            println!("cannot store the offsets of the angles: {}", e);
+            return;
+        }
+        self.save_project();
+
+        if self.current_media_path.as_ref() == Some(&reference_path) {
+            for angle in self.angles.iter_mut() {
+                if let Some(alignment) = alignments
+                    .iter()
+                    .find(|alignment| alignment.media_path == angle.media_path)
+                {
+                    angle.offset_ms = alignment.offset_ms;
+                }
+            }
+            if let Some(time) = self.mdp.get_time() {
+                self.seek(time);
+            }
+        }
+        let confidence = alignments
+            .iter()
+            .map(|alignment| alignment.confidence)
+            .fold(1.0, f32::min);
+        let msg = if confidence < align::MIN_CONFIDENCE {
+            format!(
+                "angles aligned, but unreliable (confidence {:.2}), nudge them by hand",
+                confidence
+            )
+        } else {
+            format!("angles aligned, confidence {:.2}", confidence)
+        };
+        self.show_marquee(&msg);
+    }
+
+    /// Moves the active angle, or the first other angle while the current video is active,
+    /// by `delta_ms` relative to the current video and stores its new offset
+    fn nudge_angle(&mut self, delta_ms: i64) {
+        let i = self.active_angle.max(1) - 1;
+        let angle = match self.angles.get_mut(i) {
+            Some(angle) => angle,
+            None => {
+                self.show_marquee("no other angles");
+                return;
+            }
+        };
+        angle.offset_ms += delta_ms;
+        let offset_ms = angle.offset_ms;
+        if let Some(offset) = self.project.angle_offset_mut(&angle.media_path) {
+            *offset += delta_ms;
+        }
+        self.save_project();
+
+        if let (Some(mdp), Some(time)) = (self.angle_mdps.get(i), self.mdp.get_time()) {
+            mdp.set_time((time + offset_ms).max(0));
+        }
+        let msg = format!("angle offset {:+.3} s", offset_ms as f64 / 1000.0);
+        self.show_marquee(&msg);
     }
 
-    pub(super) fn set_cutmarks(&mut self, cutmarks: Box<Cutmarks>) {
-        self.get_current_media_metadata_mut().unwrap().cutmarks = Some(cutmarks);
+    /// Which angles are played and how they are shown
+    pub(super) fn angle_view(&self) -> AngleView {
+        AngleView {
+            count: 1 + self.angle_players().count(),
+            active: self.active_angle,
+            layout: self.angle_layout,
+        }
+    }
+
+    /// Shows a message on the video of the active angle
+    fn show_marquee(&self, text: &str) {
+        let mdp = match self.active_angle {
+            0 => &self.mdp,
+            i => &self.angle_mdps[i - 1],
+        };
+        mdp.show_marqee_text(text, &self.marquee_option).unwrap();
+    }
+
+    /// Writes the project file. Has to be called after every change of the project's metadata.
+    fn save_project(&self) {
+        if let Err(e) = self.project.save() {
+            println!("error saving project file: {}", e);
+            self.show_marquee("error saving project");
+        }
+    }
+
+    pub(super) fn set_cut_mode(&mut self, mode: CutMode) {
+        self.project.set_cut_mode(mode);
+        self.save_project();
+    }
+
+    /// Stores the cutmarks AutoCutMarks found in `media_path`, which may not be the current
+    /// video anymore or even belong to a project which was open before
+    pub(super) fn set_cutmarks(&mut self, media_path: &Path, cutmarks: Box<Cutmarks>) {
+        let project_dir = media_path.parent().unwrap_or_else(|| Path::new(""));
+        if project_dir != self.project.dir() {
+            let result = update_project_file(project_dir, |project| {
+                if let Some(metadata) = project.media_metadata_mut(media_path) {
+                    metadata.cutmarks = Some(cutmarks);
+                }
+            });
+            if let Err(e) = result {
+                println!("could not store the cutmarks of {:?}: {}", media_path, e);
+            }
+            return;
+        }
+        if let Some(metadata) = self.project.media_metadata_mut(media_path) {
+            metadata.cutmarks = Some(cutmarks);
+            self.save_project();
+        }
+        if self.current_media_path.as_deref() == Some(media_path) {
+            self.show_marquee("cutmarks ready");
+        }
+    }
+
+    /// Returns the start and the clip the current position belongs to,
+    /// i.e. the last clip that starts before the current position.
+    pub(super) fn get_current_clip(&self) -> Option<(i64, &Clip)> {
+        let cur_time = self.mdp.get_time()?;
+        self.get_current_media_metadata()?
+            .clips
+            .range(..=cur_time)
+            .next_back()
+            .map(|(start, clip)| (*start, clip))
+    }
+
+    pub(super) fn set_clip_annotation(
+        &mut self,
+        media_path: &Path,
+        start: i64,
+        annotation: PlayAnnotation,
+    ) -> Result<(), String> {
+        let clip = self
+            .project
+            .media_metadata_mut(media_path)
+            .and_then(|metadata| metadata.clips.get_mut(&start))
+            .ok_or_else(|| format!("no clip at {} in {:?}", start, media_path))?;
+        clip.annotation = annotation;
+        self.save_project();
+        Ok(())
+    }
+
+    /// Replaces the reels of the project and saves it
+    pub(super) fn set_reels(&mut self, reels: Vec<Reel>) {
+        *self.project.reels_mut() = reels;
+        self.save_project();
+    }
+
+    /// Renders a reel of the project into the reels directory in the background
+    pub(super) fn render_reel(&mut self, reel: &Reel) -> Result<(), String> {
+        self.jobs.render_reel(&self.project, reel)?;
+        self.show_marquee("preparing reel");
+        Ok(())
+    }
+
+    /// Returns the updates of all background jobs since the last call.
+    /// Clips whose cut succeeded are added to the project.
+    pub(super) fn poll_jobs(&mut self) -> Vec<JobUpdate> {
+        self.apply_alignments();
+        self.add_recovered_clips();
+        match self.jobs.submit_prepared_concat() {
+            Some(Ok(count)) => {
+                let msg = format!("concatenating {} videos", count);
+                self.show_marquee(&msg);
+            }
+            Some(Err(e)) => {
+                println!("cannot concatenate clips: {}", e);
+                self.show_marquee("error concatenating");
+            }
+            None => {}
+        }
+        for (name, reel_video) in self.jobs.submit_prepared_reels() {
+            match reel_video {
+                Ok(reel_video) => {
+                    println!(
+                        "rendering reel \"{}\" into {:?}: {} clips, {}",
+                        name, reel_video.path, reel_video.clip_count, reel_video.strategy
+                    );
+                    self.show_marquee("rendering reel");
+                }
+                Err(e) => {
+                    println!("cannot render reel \"{}\": {}", name, e);
+                    self.show_marquee("error rendering reel");
+                }
+            }
+        }
+        let updates = self.jobs.queue.poll();
+        for update in updates.iter() {
+            self.handle_job_update(update);
+        }
+        updates
+    }
+
+    pub(super) fn cancel_job(&self, id: JobId) {
+        self.jobs.queue.cancel(id);
+    }
+
+    /// Blocks until all background jobs are finished, e.g. before exiting
+    pub(super) fn wait_for_jobs(&mut self) {
+        if self.jobs.queue.unfinished_count() > 0 {
+            println!(
+                "waiting for {} background jobs to finish...",
+                self.jobs.queue.unfinished_count()
+            );
+        }
+        for update in self.jobs.queue.wait_all() {
+            self.handle_job_update(&update);
+        }
+    }
+
+    fn handle_job_update(&mut self, update: &JobUpdate) {
+        if self.jobs.finish(update, &mut self.project) {
+            self.save_project();
+        }
+        let msg = match &update.status {
+            JobStatus::Succeeded => format!("done: {}", update.description),
+            JobStatus::Failed(e) => {
+                println!("job \"{}\" failed: {}", update.description, e);
+                format!("FAILED: {}", update.description)
+            }
+            JobStatus::Cancelled => format!("cancelled: {}", update.description),
+            JobStatus::Queued | JobStatus::Running(_) => return,
+        };
+
+        self.show_marquee(&msg);
     }
 
     pub(super) fn get_media_relative_position(&self) -> f32 {
@@ -150,15 +1320,90 @@ impl<'vlc> ActionHandler<'vlc> {
     }
 
     pub(super) fn set_media_relative_position(&self, pos: f32) {
-        self.mdp.set_position(pos);
+        match self.mdp.get_media().and_then(|md| md.duration()) {
+            Some(duration) => self.seek((duration as f64 * pos as f64) as i64),
+            None => self.mdp.set_position(pos),
+        }
     }
 
-    pub(super) fn check_loop_end(&self) {
-        if self.loop_end != -1 && self.mdp.get_time().unwrap() >= self.loop_end {
-            self.mdp.set_time(self.loop_start);
+    /// Jumps back to the start of the loop at its end, as often and at the speed set in the
+    /// project's `LoopOptions`. Scrubbing may leave the loop.
+    pub(super) fn check_loop_end(&mut self) {
+        if self.loop_end == -1 || self.shuttle.is_some() {
+            return;
+        }
+        let time = match self.mdp.get_time() {
+            Some(time) => time,
+            None => return,
+        };
+        let range = (self.loop_start, self.loop_end);
+        let rate = self.mdp.get_rate();
+        let progress = match &mut self.loop_progress {
+            Some(progress) if progress.range == range => progress,
+            _ => self
+                .loop_progress
+                .get_or_insert(LoopProgress::new(range, rate)),
+        };
+
+        if let Some(resume_at) = progress.resume_at {
+            if Instant::now() >= resume_at {
+                progress.resume_at = None;
+                self.play();
+            }
+            return;
+        }
+        if progress.finished {
+            progress.finished = time >= self.loop_end;
+            return;
+        }
+        let restarting = match progress.restarted {
+            Some(restarted) => restarted.elapsed() < LOOP_RESTART_GRACE,
+            None => false,
+        };
+        if time < self.loop_end || restarting {
+            return;
+        }
+
+        let options = self.project.loop_options();
+        match progress.loop_ended(&options, rate) {
+            LoopStep::Repeat {
+                rate: next_rate,
+                pause,
+            } => {
+                if let Some(pause) = pause {
+                    progress.resume_at = Some(Instant::now() + pause);
+                    self.pause();
+                }
+                self.seek(self.loop_start);
+                if (next_rate - rate).abs() > f32::EPSILON {
+                    self.set_rate(next_rate);
+                }
+            }
+            LoopStep::Finish {
+                rate: next_rate,
+                after,
+            } => {
+                if (next_rate - rate).abs() > f32::EPSILON {
+                    self.set_rate(next_rate);
+                }
+                let next = match after {
+                    AfterLoop::Continue => return,
+                    AfterLoop::NextClip => Action::NextClip,
+                    AfterLoop::NextCutmark => Action::NextCutmark,
+                };
+                // the next clip or cutmark is after the loop, which would jump back
+                self.loop_end = -1;
+                if let Err(e) = self.handle(next) {
+                    println!("cannot leave the loop: {}", e);
+                }
+            }
         }
     }
 
+    pub(super) fn project(&self) -> &Project {
+        &self.project
+    }
+
     pub(super) fn get_current_media_path(&self) -> Option<&PathBuf> {
         self.current_media_path.as_ref()
     }
@@ -168,6 +1413,17 @@ impl<'vlc> ActionHandler<'vlc> {
         unsafe { vlc::sys::libvlc_media_player_get_fps(self.mdp.raw()) }
     }
 
+    /// Duration of one frame of the current video. 25 fps are assumed while VLC does not know
+    /// the frame rate yet.
+    fn frame_duration_ms(&self) -> i64 {
+        let fps = self.get_fps();
+        if fps > 0.0 {
+            (1000.0 / fps).round() as i64
+        } else {
+            40
+        }
+    }
+
     pub(super) fn get_current_frame(&self) -> i64 {
         let time = self.mdp.get_time().unwrap() as f32; // in millisecons
         let fps = self.get_fps();
@@ -177,169 +1433,251 @@ impl<'vlc> ActionHandler<'vlc> {
     }
 
     pub(super) fn handle(&mut self, action: Action) -> Result<(), &'static str> {
+        // while reviewing, the clip actions move within the reviewed clips
+        if let Some(review) = &self.review {
+            let index = match action {
+                Action::NextClip => Some(review.current + 1),
+                Action::PreviousClip => Some(review.current.saturating_sub(1)),
+                Action::RestartClip => Some(review.current),
+                Action::NextMedia | Action::PreviousMedia | Action::RestartMedia => {
+                    self.review = None;
+                    None
+                }
+                _ => None,
+            };
+            if let Some(index) = index {
+                if index < self.review_position().map(|(_, count)| count).unwrap_or(0) {
+                    self.play_review_clip(index);
+                }
+                return Ok(());
+            }
+        }
+
         match action {
             Action::TogglePlayPause => {
-                if self.mdp.is_playing() {
-                    self.mdp.pause();
+                // ends the pause between two repetitions of the loop
+                if let Some(progress) = &mut self.loop_progress {
+                    progress.resume_at = None;
+                }
+                if self.is_playing_reverse() {
+                    self.shuttle = None;
+                } else if self.mdp.is_playing() {
+                    self.pause();
                 } else {
-                    self.mdp.play().unwrap();
+                    self.play();
                 }
             }
             Action::Forward(speed) => {
-                /*//self.mdp.pause();
-                let mut y;
-                if speed < 0.1 {
-                    y = 0.0;
-                } else if speed >= 0.1 {
-                    y = 0.1
-                } else if speed >= 0.2 {
-                    y = 0.2
-                } else if speed >= 0.3 {
-                    y = 0.3
-                } else if speed >= 0.4 {
-                    y = 0.6
-                } else if speed >= 0.5 {
-                    y = 0.8
-                } else if speed >= 0.6 {
-                    y = 1.0
-                } else if speed >= 0.7 {
-                    y = 2.0
-                } else if speed >= 0.8 {
-                    y = 4.0
-                } else if speed >= 0.9 {
-                    y = 8.0
-                } else if speed >= 1.0 {
-                    y = 16.0;
-                }
-                let cur_time = self.mdp.get_time().unwrap();
-                self.mdp.set_time(cur_time + speed as i64 * 10);
-                //self.mdp.pause();*/
                 let new_time = self.mdp.get_time().unwrap() + (speed * 1000.0) as i64;
-                self.mdp.set_time(new_time);
+                self.seek(new_time);
             }
 
             Action::Rewind(speed) => {
                 let new_time = self.mdp.get_time().unwrap() - (speed * 1000.0) as i64;
-                self.mdp.set_time(new_time);
+                self.seek(new_time);
             }
 
-            Action::IncreaseSpeed => {
-                let current_speed = self.mdp.get_rate();
-                self.mdp.set_rate(current_speed + 0.1).unwrap();
+            Action::Shuttle(speed) => self.set_shuttle_speed(speed),
+
+            Action::ReviewClips(tag_names) => {
+                if self.review.is_some() {
+                    self.review = None;
+                    self.show_marquee("review stopped");
+                } else {
+                    self.start_review(&tag_names);
+                }
             }
 
-            Action::DecreaseSpeed => {
-                let current_speed = self.mdp.get_rate();
-                self.mdp.set_rate(current_speed - 0.1).unwrap();
+            Action::CycleLoopOptions => {
+                let options = self.project.next_loop_options();
+                self.loop_progress = None;
+                self.save_project();
+                self.show_marquee(&options.to_string());
             }
 
-            Action::ConcatClips => {
-                let mut clips_dir_path = self.project_dir.clone();
-                clips_dir_path.push("_clips");
-                if !clips_dir_path.exists() {
-                    std::fs::create_dir(&clips_dir_path).expect("unable to create directory");
-                }
-
-                let mut condensed_dir_path = self.project_dir.clone();
-                condensed_dir_path.push("_condensed");
-                std::fs::create_dir(&condensed_dir_path).unwrap();
-                self.mdp
-                    .show_marqee_text("start concatenating clips", &self.marquee_option)
-                    .unwrap();
-                let result = ffmpeg::concat(&clips_dir_path, &condensed_dir_path);
-                let msg = if let Err(e) = result {
-                    println!("{}", e);
-                    "error concatenating"
+            Action::PlayReverse => {
+                if self.is_playing_reverse() {
+                    self.shuttle = None;
+                    self.play();
                 } else {
-                    "successfully concatenated clips"
-                };
+                    self.play_reverse();
+                }
+            }
 
-                self.mdp
-                    .show_marqee_text(msg, &self.marquee_option)
-                    .unwrap();
-            }
-
-            Action::CutCurrentLoop(o_d_option) => {
-                let loop_start = self.loop_start;
-                self.get_current_media_metadata_mut()
-                    .unwrap()
-                    .clips
-                    .insert(loop_start);
-                println!(
-                    "cutting from {:?} to {:?}...",
-                    self.loop_start, self.loop_end
-                );
-
-                let mut clips_dir_path = self.project_dir.clone();
-                clips_dir_path.push("_clips");
-                if !clips_dir_path.exists() {
-                    std::fs::create_dir(&clips_dir_path).expect("unable to create directory");
-                }
-
-                let mut extension = "";
-                let current_media_path = self.current_media_path.as_ref().unwrap();
-                if let Some(s) = current_media_path.extension() {
-                    if let Some(ext) = s.to_str() {
-                        extension = ext;
-                    }
+            Action::IncreaseSpeed => self.set_rate(self.playback.faster(self.mdp.get_rate())),
+
+            Action::DecreaseSpeed => self.set_rate(self.playback.slower(self.mdp.get_rate())),
+
+            Action::ResetSpeed => self.set_rate(1.0),
+
+            Action::NextFrame => {
+                self.pause();
+                // libvlc steps every player by one frame of its own video
+                unsafe { vlc::sys::libvlc_media_player_next_frame(self.mdp.raw()) };
+                for (mdp, _) in self.angle_players() {
+                    unsafe { vlc::sys::libvlc_media_player_next_frame(mdp.raw()) };
                 }
+            }
 
-                assert!(self.loop_start < i64::pow(10, 8));
-                // timestamp formatted with 8 digits to be able to store 24h.
-                let mut out_file_name = format!(
-                    "{}_{:0>8}",
-                    current_media_path.file_name().unwrap().to_str().unwrap(),
-                    self.loop_start.to_string()
-                );
-                let user_hint = if let Some(off_def) = o_d_option {
-                    match off_def {
-                        ClipType::Offense => {
-                            out_file_name.push_str(CLIP_SUFFIX_OFFENSE);
-                            " as Offense"
-                        }
-                        ClipType::Defense => {
-                            out_file_name.push_str(CLIP_SUFFIX_DEFENSE);
-                            " as Defense"
-                        }
+            Action::PreviousFrame => {
+                // libvlc cannot step backwards, so seek to one frame earlier instead
+                self.pause();
+                if let Some(time) = self.mdp.get_time() {
+                    self.seek((time - self.frame_duration_ms()).max(0));
+                }
+            }
+
+            Action::ConcatClips => {
+                // the clips are probed in the background, see `poll_jobs`
+                let msg = match self.jobs.concat_clips(&self.project) {
+                    Ok(()) => "preparing concatenation".to_owned(),
+                    Err(e) => {
+                        println!("cannot concatenate clips: {}", e);
+                        e
                     }
-                } else {
-                    ""
                 };
-                out_file_name = out_file_name + "." + extension;
-                let out_file_path = clips_dir_path.join(out_file_name);
-
-                assert!(self.loop_start >= 0 && self.loop_end > self.loop_start);
-
-                let start = self.loop_start as f32 / 1000.0;
-                let end = self.loop_end as f32 / 1000.0;
-                let duration = end - start;
-
-                if let Ok(child_proc) = Command::new("ffmpeg")
-                    .arg("-ss")
-                    .arg(format!("{}", start))
-                    .arg("-i")
-                    .arg(current_media_path)
-                    .arg("-t")
-                    .arg(format!("{}", duration))
-                    .arg("-c")
-                    .arg("copy")
-                    .arg(out_file_path)
-                    .spawn()
-                {
-                    let msg = "cut clip".to_owned() + user_hint;
-                    self.mdp
-                        .show_marqee_text(&msg, &self.marquee_option)
-                        .unwrap();
-                    println!("command executed: {:?}", child_proc);
+                self.show_marquee(&msg);
+            }
+
+            Action::CutCurrentLoop(tag_names) => {
+                let (loop_start, loop_end) = match loop_range(self.loop_start, self.loop_end) {
+                    Some(range) => range,
+                    None => {
+                        self.show_marquee("no loop set");
+                        return Ok(());
+                    }
+                };
+                println!("cutting from {:?} to {:?}...", loop_start, loop_end);
+
+                let user_hint = if tag_names.is_empty() {
+                    "".to_owned()
                 } else {
-                    self.mdp
-                        .show_marqee_text("error on creating clip", &self.marquee_option)
-                        .unwrap();
+                    format!(" as {}", tag_names.join("+"))
+                };
+                let current_media_path = self.current_media_path.clone().unwrap();
+                if let Err(e) = self.jobs.cut_clip(
+                    &self.project,
+                    &current_media_path,
+                    loop_start,
+                    loop_end,
+                    tag_names.clone(),
+                ) {
+                    println!("cannot cut clip: {}", e);
+                    self.show_marquee(&e);
+                    return Ok(());
                 }
 
+                // the same moment of the game from the other angles
+                let mut angle_count = 1;
+                for angle in self.angles.iter() {
+                    let start = loop_start + angle.offset_ms;
+                    let end = loop_end + angle.offset_ms;
+                    if end <= 0 {
+                        println!("{:?} starts after the loop", angle.media_path);
+                        continue;
+                    }
+                    match self.jobs.cut_clip(
+                        &self.project,
+                        &angle.media_path,
+                        start.max(0),
+                        end,
+                        tag_names.clone(),
+                    ) {
+                        Ok(_) => angle_count += 1,
+                        Err(e) => println!("cannot cut clip of {:?}: {}", angle.media_path, e),
+                    }
+                }
+
+                let mut msg = "cutting clip".to_owned() + &user_hint;
+                if angle_count > 1 {
+                    msg += &format!(" from {} angles", angle_count);
+                }
+                self.show_marquee(&msg);
+
                 self.loop_start = -1;
                 self.loop_end = -1;
             }
+            Action::NextAngle => {
+                let count = self.angle_view().count;
+                if count > 1 {
+                    self.active_angle = (self.active_angle + 1) % count;
+                    self.mute_inactive_angles();
+                    let media_path = match self.active_angle {
+                        0 => self.current_media_path.clone().unwrap(),
+                        i => self.angles[i - 1].media_path.clone(),
+                    };
+                    let msg = format!(
+                        "angle {}/{}: {}",
+                        self.active_angle + 1,
+                        count,
+                        media_path.file_name().unwrap_or_default().to_string_lossy()
+                    );
+                    self.show_marquee(&msg);
+                } else {
+                    self.show_marquee("no other angles");
+                }
+            }
+
+            Action::ExportPreview => {
+                let media_path = self.current_media_path.clone().unwrap();
+                let preview = if let Some((start, end)) = loop_range(self.loop_start, self.loop_end)
+                {
+                    let file_name = clip_file_name(&media_path, start, &[]);
+                    Some((start, end, file_name))
+                } else {
+                    match self.get_current_clip() {
+                        Some((start, clip)) => clip.end.map(|end| {
+                            let clip_path = self.project.clip_file_path(&media_path, start, clip);
+                            let file_name = clip_path.file_name().unwrap().to_string_lossy();
+                            (start, end, file_name.into_owned())
+                        }),
+                        None => None,
+                    }
+                };
+                let msg = match preview {
+                    Some((start, end, file_name)) => {
+                        let stem = Path::new(&file_name).file_stem().unwrap().to_string_lossy();
+                        match self.jobs.export_preview(
+                            &self.project,
+                            &media_path,
+                            start,
+                            end,
+                            &stem,
+                        ) {
+                            Ok(_) => "exporting preview".to_owned(),
+                            Err(e) => {
+                                println!("cannot export preview: {}", e);
+                                e
+                            }
+                        }
+                    }
+                    None => "set a loop or go to a clip to export a preview".to_owned(),
+                };
+                self.show_marquee(&msg);
+            }
+
+            Action::AlignAngles => {
+                let msg = match self.align_angles() {
+                    Ok(()) => "aligning angles by their audio".to_owned(),
+                    Err(e) => {
+                        println!("cannot align angles: {}", e);
+                        e
+                    }
+                };
+                self.show_marquee(&msg);
+            }
+
+            Action::NudgeAngleForward => self.nudge_angle(ANGLE_NUDGE_MS),
+
+            Action::NudgeAngleBack => self.nudge_angle(-ANGLE_NUDGE_MS),
+
+            Action::ToggleAngleLayout => {
+                self.angle_layout = match self.angle_layout {
+                    AngleLayout::Single => AngleLayout::SideBySide,
+                    AngleLayout::SideBySide => AngleLayout::Single,
+                };
+            }
+
             Action::StartLoop => {
                 match self.mdp.get_time() {
                     Some(start) => {
@@ -350,23 +1688,21 @@ impl<'vlc> ActionHandler<'vlc> {
                     }
                     None => println!("error getting time"),
                 }
-                self.mdp
-                    .show_marqee_text("start loop", &self.marquee_option)
-                    .unwrap();
+                self.show_marquee("start loop");
                 println!("set loop start at {:?}", self.loop_start)
             }
 
             Action::PreviousClip => {
                 let clips = &self.get_current_media_metadata().unwrap().clips;
                 let cur_time = self.mdp.get_time().unwrap();
-                let mut iter = clips.iter().rev();
+                let mut iter = clips.keys().rev();
                 while let Some(clip) = iter.next() {
                     if clip <= &cur_time {
                         if let Some(prev_clip) = iter.next() {
-                            self.mdp.set_time(*prev_clip);
+                            self.seek(*prev_clip);
                             println!("previous clip from {}", *prev_clip);
                         } else {
-                            self.mdp.set_time(*clip);
+                            self.seek(*clip);
                             println!("previous clip from {}", *clip);
                         }
                         break;
@@ -377,9 +1713,9 @@ impl<'vlc> ActionHandler<'vlc> {
             Action::NextClip => {
                 let clips = &self.get_current_media_metadata().unwrap().clips;
                 let cur_time = self.mdp.get_time().unwrap();
-                for clip in &mut clips.iter() {
+                for clip in &mut clips.keys() {
                     if clip >= &cur_time {
-                        self.mdp.set_time(*clip);
+                        self.seek(*clip);
                         println!("jumping to clip {}", *clip);
                         break;
                     }
@@ -392,9 +1728,9 @@ impl<'vlc> ActionHandler<'vlc> {
                     self.handle(Action::RestartMedia)?
                 } else {
                     let cur_time = self.mdp.get_time().unwrap();
-                    for clip in &mut clips.iter().rev() {
+                    for clip in &mut clips.keys().rev() {
                         if clip <= &cur_time {
-                            self.mdp.set_time(*clip);
+                            self.seek(*clip);
                             println!("restarting clip from to {}", *clip);
                             break;
                         }
@@ -410,15 +1746,13 @@ impl<'vlc> ActionHandler<'vlc> {
                     while let Some(cutmark) = iter.next() {
                         if cutmark <= &cur_time {
                             if let Some(prev_cutmark) = iter.next() {
-                                self.mdp.set_time(*prev_cutmark);
+                                self.seek(*prev_cutmark);
                                 println!("previous cutmark from {}", *prev_cutmark);
                             } else {
-                                self.mdp.set_time(*cutmark);
+                                self.seek(*cutmark);
                                 println!("previous cutmark from {}", *cutmark);
                             }
-                            self.mdp
-                                .show_marqee_text("Previous Cutmark", &self.marquee_option)
-                                .unwrap();
+                            self.show_marquee("Previous Cutmark");
                             //self.mdp.play();
                             //tx.send(Action::TogglePlayPause).unwrap();
                             break;
@@ -432,13 +1766,11 @@ impl<'vlc> ActionHandler<'vlc> {
                 if let Some(cutmarks) = &self.get_current_media_metadata().unwrap().cutmarks {
                     for cutmark in &mut cutmarks.iter() {
                         if cutmark > &cur_time {
-                            self.mdp.set_time(*cutmark);
-                            self.mdp.play().unwrap();
+                            self.seek(*cutmark);
+                            self.play();
                             //tx.send(Action::TogglePlayPause).unwrap();
                             println!("jumping to cutmark {}", *cutmark);
-                            self.mdp
-                                .show_marqee_text("Next Cutmark", &self.marquee_option)
-                                .unwrap();
+                            self.show_marquee("Next Cutmark");
                             break;
                         }
                     }
@@ -457,10 +1789,8 @@ impl<'vlc> ActionHandler<'vlc> {
                     None => println!("error getting time"),
                 }
                 println!("set loop end at {:?}", self.loop_end);
-                self.mdp
-                    .show_marqee_text("end loop", &self.marquee_option)
-                    .unwrap();
-                self.mdp.set_time(self.loop_start);
+                self.show_marquee("end loop");
+                self.seek(self.loop_start);
                 //check_self.loop_end(&tx, self.mdp, self.loop_start, self.loop_end);
             }
 
@@ -476,9 +1806,7 @@ impl<'vlc> ActionHandler<'vlc> {
                 }
             }*/
             Action::BreakLoop => {
-                self.mdp
-                    .show_marqee_text("break loop", &self.marquee_option)
-                    .unwrap();
+                self.show_marquee("break loop");
                 self.loop_end = -1;
             }
 
@@ -503,7 +1831,7 @@ impl<'vlc> ActionHandler<'vlc> {
             }
 
             Action::RestartMedia => {
-                self.mdp.set_time(0);
+                self.seek(0);
             }
 
             Action::Exit => return Err("No real error. Just exiting"),
@@ -512,3 +1840,403 @@ impl<'vlc> ActionHandler<'vlc> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+// `ActionHandler` itself owns VLC media players, which cannot be created without libvlc, so
+// these tests cover the parts of it which do not need VLC
+mod test {
+    use super::*;
+    use crate::media::{MediaCall, RecordingProcessor};
+
+    #[test]
+    fn test_cut_clip() {
+        let project_dir = Path::new("tests").join("output").join("media_jobs_cut");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        let mut project = Project::load(&project_dir).unwrap();
+        let media_path = project_dir.join("game.mp4");
+        let failing_output = project.clips_dir().join("game.mp4_00009000Def.mp4");
+        let mut processor = RecordingProcessor::default();
+        processor.failing_outputs.insert(failing_output.clone());
+        let processor = Arc::new(processor);
+        let mut jobs = MediaJobs::new(processor.clone());
+
+        jobs.cut_clip(
+            &project,
+            &media_path,
+            1000,
+            5000,
+            vec!["Offense".to_owned()],
+        )
+        .unwrap();
+        jobs.cut_clip(
+            &project,
+            &media_path,
+            9000,
+            12000,
+            vec!["Defense".to_owned()],
+        )
+        .unwrap();
+        assert!(jobs
+            .cut_clip(
+                &project,
+                &media_path,
+                20000,
+                21000,
+                vec!["Kickoff".to_owned()]
+            )
+            .is_err());
+        assert_eq!(jobs.pending_files(&project).len(), 2);
+
+        let changes = jobs
+            .queue
+            .wait_all()
+            .iter()
+            .filter(|update| jobs.finish(update, &mut project))
+            .count();
+        assert_eq!(changes, 1);
+        assert!(jobs.pending_files(&project).is_empty());
+        let clips = &project.media_metadata(&media_path).unwrap().clips;
+        assert_eq!(clips.keys().collect::<Vec<_>>(), vec![&1000]);
+        assert_eq!(clips[&1000].end, Some(5000));
+
+        let calls = processor.calls();
+        assert_eq!(
+            calls[0],
+            MediaCall::Cut {
+                input: media_path.clone(),
+                start_ms: 1000,
+                end_ms: 5000,
+                output: project.clips_dir().join("game.mp4_00001000Off.mp4"),
+                mode: CutMode::StreamCopy,
+            }
+        );
+        assert!(calls.contains(&MediaCall::Run(failing_output)));
+    }
+
+    #[test]
+    fn test_cut_clip_after_project_switch() {
+        let output_dir = Path::new("tests").join("output").join("media_jobs_switch");
+        let _ = std::fs::remove_dir_all(&output_dir);
+        let first_dir = output_dir.join("first");
+        let second_dir = output_dir.join("second");
+        std::fs::create_dir_all(&first_dir).unwrap();
+        std::fs::create_dir_all(&second_dir).unwrap();
+        let first = Project::load(&first_dir).unwrap();
+        let mut jobs = MediaJobs::new(Arc::new(RecordingProcessor::default()));
+        jobs.cut_clip(&first, &first_dir.join("game.mp4"), 1000, 5000, Vec::new())
+            .unwrap();
+
+        // the user opened another project with a video of the same name in the meantime
+        let mut second = Project::load(&second_dir).unwrap();
+        assert!(jobs.pending_files(&second).is_empty());
+        let changes = jobs
+            .queue
+            .wait_all()
+            .iter()
+            .filter(|update| jobs.finish(update, &mut second))
+            .count();
+        assert_eq!(changes, 0);
+        assert!(second
+            .media_metadata(&second_dir.join("game.mp4"))
+            .is_none());
+        let first = Project::load(&first_dir).unwrap();
+        let clips = &first
+            .media_metadata(&first_dir.join("game.mp4"))
+            .unwrap()
+            .clips;
+        assert_eq!(clips[&1000].end, Some(5000));
+    }
+
+    #[test]
+    fn test_probe_clips() {
+        let project_dir = Path::new("tests").join("output").join("media_jobs_recover");
+        let _ = std::fs::remove_dir_all(&project_dir);
+        let mut project = Project::load(&project_dir).unwrap();
+        std::fs::create_dir_all(project.clips_dir()).unwrap();
+        let clip_path = project.clips_dir().join("game.mp4_00002000Off.mp4");
+        std::fs::write(&clip_path, "").unwrap();
+        let mut processor = RecordingProcessor::default();
+        processor.formats.insert(
+            clip_path.clone(),
+            ffmpeg::MediaFormat {
+                duration_ms: 3000,
+                ..Default::default()
+            },
+        );
+        let mut jobs = MediaJobs::new(Arc::new(processor));
+
+        let (unknown_clips, problems) = project.unknown_clips(&[]);
+        assert!(problems.is_empty());
+        jobs.probe_clips(unknown_clips);
+        let probed_clips = loop {
+            if let Some(probed_clips) = jobs.probed_clips() {
+                break probed_clips;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        };
+        assert!(jobs.probed_clips().is_none());
+        assert_eq!(
+            project.add_recovered_clips(&[], probed_clips),
+            (1, Vec::new())
+        );
+        let clips = &project
+            .media_metadata(&project_dir.join("game.mp4"))
+            .unwrap()
+            .clips;
+        assert_eq!(clips[&2000].end, Some(5000));
+        assert_eq!(clips[&2000].tags, vec!["Offense".to_owned()]);
+    }
+
+    #[test]
+    fn test_concat_clips() {
+        let project_dir = Path::new("tests").join("output").join("media_jobs_concat");
+        let project = Project::load(&project_dir).unwrap();
+        let condensed_dir_path = project_dir.join("_condensed");
+        std::fs::create_dir_all(project.clips_dir()).unwrap();
+        let mut processor = RecordingProcessor::default();
+        for file_name in ["game.mp4_00001000Off.mp4", "game.mp4_00002000Def.mp4"].iter() {
+            let clip_path = project.clips_dir().join(file_name);
+            std::fs::write(&clip_path, "").unwrap();
+            processor.formats.insert(clip_path, Default::default());
+        }
+        processor
+            .failing_outputs
+            .insert(condensed_dir_path.join("condensed_defense.mp4"));
+        let processor = Arc::new(processor);
+        let mut jobs = MediaJobs::new(processor);
+
+        jobs.concat_clips(&project).unwrap();
+        let submitted = loop {
+            if let Some(submitted) = jobs.submit_prepared_concat() {
+                break submitted;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        };
+        assert_eq!(submitted, Ok(3));
+        assert_eq!(jobs.submit_prepared_concat(), None);
+
+        let mut project = project;
+        let updates = jobs.queue.wait_all();
+        for update in updates.iter() {
+            assert!(!jobs.finish(update, &mut project));
+        }
+        let failed: Vec<&str> = updates
+            .iter()
+            .filter(|update| matches!(update.status, JobStatus::Failed(_)))
+            .map(|update| update.description.as_str())
+            .collect();
+        assert_eq!(failed, vec!["concatenate condensed_defense"]);
+        assert!(jobs.condensed_videos.is_empty());
+        // neither index files nor the failed video are left behind
+        assert_eq!(std::fs::read_dir(&condensed_dir_path).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_render_reel() {
+        let project_dir = Path::new("tests").join("output").join("media_jobs_reel");
+        let mut project = Project::load(&project_dir).unwrap();
+        let media_path = project_dir.join("game.mp4");
+        project
+            .media_metadata_mut(&media_path)
+            .unwrap()
+            .clips
+            .insert(
+                2000,
+                Clip {
+                    end: Some(4000),
+                    ..Default::default()
+                },
+            );
+        let clip_path = project.clips_dir().join("game.mp4_00002000.mp4");
+        std::fs::create_dir_all(project.clips_dir()).unwrap();
+        std::fs::write(&clip_path, "").unwrap();
+        let reel = Reel {
+            name: "Blitz".to_owned(),
+            clips: vec![crate::project::ReelClip {
+                media: "game.mp4".to_owned(),
+                start: 2000,
+            }],
+            options: Default::default(),
+        };
+
+        let mut processor = RecordingProcessor::default();
+        processor
+            .formats
+            .insert(clip_path.clone(), Default::default());
+        let processor = Arc::new(processor);
+        let mut jobs = MediaJobs::new(processor.clone());
+        jobs.render_reel(&project, &reel).unwrap();
+        let submitted = loop {
+            let submitted = jobs.submit_prepared_reels();
+            if !submitted.is_empty() {
+                break submitted;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        };
+        assert!(jobs.preparing_reels.is_empty());
+        let (name, reel_video) = submitted.into_iter().next().unwrap();
+        assert_eq!(name, "Blitz");
+        let reel_video = reel_video.unwrap();
+        assert_eq!(reel_video.path, project.reels_dir().join("blitz.mp4"));
+        jobs.queue.wait_all();
+        assert_eq!(
+            processor.calls(),
+            vec![
+                MediaCall::Probe(clip_path.clone()),
+                MediaCall::Concat {
+                    clips: vec![clip_path],
+                    output: reel_video.path.clone(),
+                },
+                MediaCall::Run(reel_video.path),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_export_preview() {
+        let project_dir = Path::new("tests").join("output").join("media_jobs_preview");
+        let project = Project::load(&project_dir).unwrap();
+        let media_path = project_dir.join("game.mp4");
+        let processor = Arc::new(RecordingProcessor::default());
+        let mut jobs = MediaJobs::new(processor.clone());
+
+        let count = jobs
+            .export_preview(&project, &media_path, 3000, 7000, "game.mp4_00003000")
+            .unwrap();
+        assert_eq!(count, 2);
+        jobs.queue.wait_all();
+        let previews: Vec<MediaCall> = processor
+            .calls()
+            .into_iter()
+            .filter(|call| matches!(call, MediaCall::Preview { .. }))
+            .collect();
+        assert_eq!(
+            previews,
+            vec![
+                MediaCall::Preview {
+                    input: media_path.clone(),
+                    start_ms: 3000,
+                    end_ms: 7000,
+                    output: project.previews_dir().join("game.mp4_00003000.gif"),
+                },
+                MediaCall::Preview {
+                    input: media_path,
+                    start_ms: 3000,
+                    end_ms: 7000,
+                    output: project.previews_dir().join("game.mp4_00003000_sheet.jpg"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shuttle_advance() {
+        let mut reverse = Shuttle {
+            speed: -2.0,
+            position: 10000,
+            last_step: Instant::now(),
+            resume: false,
+            reverse: true,
+        };
+        assert_eq!(reverse.advance(500.0, Some(60000), None), 9000);
+        // loops backwards
+        assert_eq!(
+            reverse.advance(500.0, Some(60000), Some((8500, 9500))),
+            9500
+        );
+        assert_eq!(
+            reverse.advance(200.0, Some(60000), Some((8500, 9500))),
+            9100
+        );
+        assert_eq!(reverse.advance(10000.0, Some(60000), None), 0);
+
+        // scrubbing leaves the loop and stops at the end of the video
+        let mut forward = Shuttle {
+            speed: 4.0,
+            position: 59000,
+            last_step: Instant::now(),
+            resume: true,
+            reverse: false,
+        };
+        assert_eq!(forward.advance(100.0, Some(60000), Some((0, 59000))), 59400);
+        assert_eq!(
+            forward.advance(1000.0, Some(60000), Some((0, 59000))),
+            60000
+        );
+    }
+
+    #[test]
+    fn test_cut_loop_during_review() {
+        // `start_review` clears the end of the loop, so `CutCurrentLoop` finds no loop to cut
+        // until a new one is set instead of asserting on it
+        assert_eq!(loop_range(5000, -1), None);
+        assert_eq!(loop_range(-1, -1), None);
+        assert_eq!(loop_range(5000, 4000), None);
+        assert_eq!(loop_range(5000, 9000), Some((5000, 9000)));
+    }
+
+    #[test]
+    fn test_advance_media_iter() {
+        let media_paths: Vec<PathBuf> = ["a.mp4", "b.mp4", "c.mp4"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        let mut media_iter = media_paths.into_iter().cycle();
+        assert_eq!(media_iter.next(), Some(PathBuf::from("a.mp4")));
+
+        assert!(advance_media_iter(&mut media_iter, Path::new("a.mp4")));
+        assert_eq!(media_iter.next(), Some(PathBuf::from("b.mp4")));
+        assert!(!advance_media_iter(
+            &mut media_iter,
+            Path::new("a_endzone.mp4")
+        ));
+        assert_eq!(media_iter.next(), Some(PathBuf::from("c.mp4")));
+
+        let mut empty = Vec::<PathBuf>::new().into_iter().cycle();
+        assert!(!advance_media_iter(&mut empty, Path::new("a.mp4")));
+    }
+
+    #[test]
+    fn test_loop_progress() {
+        let teaching = LoopOptions {
+            repeat: 4,
+            after: AfterLoop::NextClip,
+            slow_motion_rate: Some(0.25),
+            pause_seconds: 1.5,
+        };
+        let pause = Some(Duration::from_millis(1500));
+        let mut progress = LoopProgress::new((1000, 5000), 1.0);
+        assert_eq!(
+            progress.loop_ended(&teaching, 1.0),
+            LoopStep::Repeat { rate: 0.25, pause }
+        );
+        assert_eq!(
+            progress.loop_ended(&teaching, 0.25),
+            LoopStep::Repeat { rate: 1.0, pause }
+        );
+        // the speed was changed during the repetition at normal speed
+        assert_eq!(
+            progress.loop_ended(&teaching, 1.5),
+            LoopStep::Repeat { rate: 0.25, pause }
+        );
+        assert_eq!(
+            progress.loop_ended(&teaching, 0.25),
+            LoopStep::Finish {
+                rate: 1.5,
+                after: AfterLoop::NextClip
+            }
+        );
+        assert!(progress.finished);
+
+        let mut forever = LoopProgress::new((1000, 5000), 2.0);
+        for _ in 0..10 {
+            assert_eq!(
+                forever.loop_ended(&LoopOptions::default(), 2.0),
+                LoopStep::Repeat {
+                    rate: 2.0,
+                    pause: None
+                }
+            );
+        }
+    }
+}
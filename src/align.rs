@@ -0,0 +1,404 @@
+//! Finds the offsets between the angles of a game by cross-correlating their audio. All cameras
+//! record the same whistles, hits and cheering, so the audio of the other angles matches the
+//! audio of the first angle best when it is shifted by the difference of their start times.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    jobs::{JobQueue, JobStatus},
+    media::MediaProcessor,
+    project::Project,
+};
+
+/// Samples per second of the extracted audio
+const SAMPLE_RATE: u32 = 8000;
+/// Length of the audio of every angle that is compared
+const AUDIO_DURATION_MS: i64 = 10 * 60 * 1000;
+/// The cameras of a game are expected to be started less than this apart
+const MAX_OFFSET_MS: i64 = 5 * 60 * 1000;
+/// Length of the windows the loudness is measured in. This is the precision of the offsets.
+const WINDOW_MS: i64 = 20;
+/// Windows which are combined into one for the rough search of the offset, see `audio_offset`
+const COARSE_WINDOWS: usize = 10;
+/// Offsets found with a lower confidence should be checked in the player and nudged by hand
+pub(crate) const MIN_CONFIDENCE: f32 = 0.3;
+
+/// The offset of an angle to the first angle of its group, see `project::Angle`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Alignment {
+    pub(crate) media_path: PathBuf,
+    /// A time `t` of the first angle corresponds to `t + offset_ms` of this angle
+    pub(crate) offset_ms: i64,
+    /// Correlation of the audio at the offset, between -1 and 1
+    pub(crate) confidence: f32,
+}
+
+impl std::fmt::Display for Alignment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {:+.3} s, confidence {:.2}",
+            self.media_path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy(),
+            self.offset_ms as f64 / 1000.0,
+            self.confidence
+        )?;
+        if self.confidence < MIN_CONFIDENCE {
+            f.write_str(" (unreliable, check it and nudge it by hand)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Extracts the audio of the videos with `processor` and computes the offset of every video to
+/// the first one. Blocks until all audio is extracted and compared.
+/// The audio is written to `work_dir` and removed afterwards.
+pub(crate) fn align_angles(
+    processor: Arc<dyn MediaProcessor>,
+    media_paths: &[PathBuf],
+    work_dir: &Path,
+) -> Result<Vec<Alignment>, String> {
+    std::fs::create_dir_all(work_dir)
+        .map_err(|e| format!("could not create {:?}: {}", work_dir, e))?;
+    let mut jobs = JobQueue::new(processor.clone());
+    let audio_paths: Vec<PathBuf> = media_paths
+        .iter()
+        .enumerate()
+        .map(|(i, media_path)| {
+            let audio_path = work_dir.join(format!("angle{}.pcm", i));
+            let description = format!(
+                "extract audio of {}",
+                media_path.file_name().unwrap_or_default().to_string_lossy()
+            );
+            jobs.submit(processor.extract_audio(
+                description,
+                media_path,
+                AUDIO_DURATION_MS,
+                SAMPLE_RATE,
+                &audio_path,
+            ));
+            audio_path
+        })
+        .collect();
+
+    let mut result = Ok(());
+    for update in jobs.wait_all() {
+        if let JobStatus::Failed(e) = update.status {
+            result = Err(format!("{}: {}", update.description, e));
+        }
+    }
+    let audio: Vec<std::io::Result<Vec<u8>>> = audio_paths
+        .iter()
+        .map(|audio_path| {
+            let content = std::fs::read(audio_path);
+            let _ = std::fs::remove_file(audio_path);
+            content
+        })
+        .collect();
+    result?;
+
+    let mut audio = audio.into_iter().zip(media_paths);
+    let reference = match audio.next() {
+        Some((content, media_path)) => {
+            content.map_err(|e| format!("could not read the audio of {:?}: {}", media_path, e))?
+        }
+        None => return Ok(Vec::new()),
+    };
+    audio
+        .map(|(content, media_path)| {
+            let content = content
+                .map_err(|e| format!("could not read the audio of {:?}: {}", media_path, e))?;
+            let (offset_ms, confidence) = audio_offset(&reference, &content)
+                .map_err(|e| format!("cannot align {:?}: {}", media_path, e))?;
+            Ok(Alignment {
+                media_path: media_path.clone(),
+                offset_ms,
+                confidence,
+            })
+        })
+        .collect()
+}
+
+/// Stores the offsets of the aligned angles in the project, relative to the offset of the video
+/// they were aligned to. Fails without changing the project if the videos are not in it, e.g.
+/// because another project was opened while they were aligned.
+pub(crate) fn store_offsets(
+    project: &mut Project,
+    reference_path: &Path,
+    alignments: &[Alignment],
+) -> Result<(), String> {
+    if let Some(media_path) = std::iter::once(reference_path)
+        .chain(
+            alignments
+                .iter()
+                .map(|alignment| alignment.media_path.as_path()),
+        )
+        .find(|media_path| !project.contains_media(media_path))
+    {
+        return Err(format!("{:?} is not a video of the project", media_path));
+    }
+    let reference_offset = project
+        .angle_offset_mut(reference_path)
+        .map(|offset| *offset)
+        .unwrap_or(0);
+    for alignment in alignments.iter() {
+        if let Some(offset) = project.angle_offset_mut(&alignment.media_path) {
+            *offset = reference_offset + alignment.offset_ms;
+        }
+    }
+    Ok(())
+}
+
+/// Finds the offset in ms of `other` to `reference`, both raw PCM as written by
+/// `MediaProcessor::extract_audio`, and the correlation at this offset
+fn audio_offset(reference_pcm: &[u8], other_pcm: &[u8]) -> Result<(i64, f32), String> {
+    let samples_per_window = (SAMPLE_RATE as i64 * WINDOW_MS / 1000) as usize;
+    let reference = onsets(&pcm_samples(reference_pcm), samples_per_window);
+    let other = onsets(&pcm_samples(other_pcm), samples_per_window);
+    let max_lag = MAX_OFFSET_MS / WINDOW_MS;
+    // Comparing every lag of 10 minutes of audio takes about 1e9 multiplications, so the offset
+    // is searched in combined windows first and only refined around the best one.
+    let factor = COARSE_WINDOWS as i64;
+    let coarse_max_lag = max_lag / factor;
+    let (coarse_lag, _) = best_lag(
+        &coarse(&reference),
+        &coarse(&other),
+        -coarse_max_lag..=coarse_max_lag,
+    )
+    .ok_or_else(|| "the audio is too short or silent".to_owned())?;
+    let lags = ((coarse_lag - 2) * factor).max(-max_lag)..=((coarse_lag + 2) * factor).min(max_lag);
+    best_lag(&reference, &other, lags)
+        .map(|(lag, correlation)| (lag * WINDOW_MS, correlation))
+        .ok_or_else(|| "the audio is too short or silent".to_owned())
+}
+
+/// Sums up the onsets of every `COARSE_WINDOWS` windows
+fn coarse(onsets: &[f32]) -> Vec<f32> {
+    onsets
+        .chunks(COARSE_WINDOWS)
+        .map(|windows| windows.iter().sum())
+        .collect()
+}
+
+fn pcm_samples(pcm: &[u8]) -> Vec<f32> {
+    pcm.chunks_exact(2)
+        .map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / i16::MAX as f32)
+        .collect()
+}
+
+/// How much louder every window of `samples_per_window` samples is than the one before,
+/// normalized to a mean of 0 and a standard deviation of 1. Cameras at different distances
+/// record the same events at very different levels, the changes of the level match better
+/// than the level itself.
+fn onsets(samples: &[f32], samples_per_window: usize) -> Vec<f32> {
+    let levels: Vec<f32> = samples
+        .chunks_exact(samples_per_window)
+        .map(|window| {
+            let energy: f32 = window.iter().map(|sample| sample * sample).sum();
+            (energy / window.len() as f32 + 1e-6).ln()
+        })
+        .collect();
+    let onsets: Vec<f32> = levels
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).max(0.0))
+        .collect();
+
+    let mean = onsets.iter().sum::<f32>() / onsets.len().max(1) as f32;
+    let variance =
+        onsets.iter().map(|x| (x - mean) * (x - mean)).sum::<f32>() / onsets.len().max(1) as f32;
+    if variance <= f32::EPSILON {
+        return Vec::new();
+    }
+    let deviation = variance.sqrt();
+    onsets.iter().map(|x| (x - mean) / deviation).collect()
+}
+
+/// Finds the lag `d` within `lags` at which `reference[t]` matches `other[t + d]` best and
+/// returns it with the correlation at it. Lags at which less than half of the shorter signal
+/// overlaps are not considered, they match by chance too easily.
+fn best_lag(
+    reference: &[f32],
+    other: &[f32],
+    lags: std::ops::RangeInclusive<i64>,
+) -> Option<(i64, f32)> {
+    let min_overlap = reference.len().min(other.len()) / 2;
+    if min_overlap == 0 {
+        return None;
+    }
+    let mut best: Option<(i64, f32)> = None;
+    for lag in lags {
+        let start = (-lag).max(0) as usize;
+        let end = (reference.len() as i64).min(other.len() as i64 - lag);
+        if end - (start as i64) < min_overlap as i64 {
+            continue;
+        }
+        let end = end as usize;
+        let sum: f32 = reference[start..end]
+            .iter()
+            .zip(&other[(start as i64 + lag) as usize..])
+            .map(|(a, b)| a * b)
+            .sum();
+        let correlation = sum / (end - start) as f32;
+        match best {
+            Some((_, best_correlation)) if best_correlation >= correlation => {}
+            _ => best = Some((lag, correlation)),
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::media::{MediaCall, RecordingProcessor};
+
+    /// Raw PCM of a recording with a loud bang at each of the given times in ms, starting
+    /// `start_ms` into the game. `gain` simulates the distance of the camera.
+    fn recording(bang_times_ms: &[i64], start_ms: i64, duration_ms: i64, gain: f32) -> Vec<u8> {
+        let sample_count = (duration_ms * SAMPLE_RATE as i64 / 1000) as usize;
+        let mut samples = vec![0.0f32; sample_count];
+        // some noise, so the recording is never silent
+        let mut noise_state: u32 = 12345;
+        for sample in samples.iter_mut() {
+            noise_state = noise_state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            *sample = ((noise_state >> 16) as f32 / 65536.0 - 0.5) * 0.01;
+        }
+        for bang_ms in bang_times_ms.iter().map(|time| time - start_ms) {
+            let first = (bang_ms * SAMPLE_RATE as i64 / 1000).max(0) as usize;
+            for (i, sample) in samples.iter_mut().skip(first).take(800).enumerate() {
+                *sample += if i % 2 == 0 { 0.8 } else { -0.8 } * (1.0 - i as f32 / 800.0);
+            }
+        }
+        samples
+            .iter()
+            .flat_map(|sample| {
+                ((sample * gain * i16::MAX as f32) as i16)
+                    .to_le_bytes()
+                    .to_vec()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_best_lag() {
+        let reference = vec![0.0, 1.0, 0.0, 0.0, 2.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+        let mut other = vec![0.0; 3];
+        other.extend(reference.iter().copied());
+        assert_eq!(
+            best_lag(&reference, &other, -5..=5).map(|(lag, _)| lag),
+            Some(3)
+        );
+        assert_eq!(
+            best_lag(&other, &reference, -5..=5).map(|(lag, _)| lag),
+            Some(-3)
+        );
+        // only the given lags are compared
+        assert!(matches!(
+            best_lag(&reference, &other, -5..=2),
+            Some((lag, _)) if lag <= 2
+        ));
+        assert_eq!(best_lag(&[], &other, -5..=5), None);
+    }
+
+    #[test]
+    fn test_audio_offset() {
+        let bangs = [2000, 3100, 5700, 6400, 9900, 12300, 15000];
+        let sideline = recording(&bangs, 0, 20000, 1.0);
+        // the endzone camera was started 1.5 s later and is further away
+        let endzone = recording(&bangs, 1500, 20000, 0.3);
+        let (offset_ms, confidence) = audio_offset(&sideline, &endzone).unwrap();
+        assert_eq!(offset_ms, -1500);
+        assert!(confidence > MIN_CONFIDENCE);
+
+        let silence = vec![0u8; 320000];
+        assert!(audio_offset(&sideline, &silence).is_err());
+    }
+
+    #[test]
+    fn test_align_angles() {
+        let work_dir = Path::new("tests").join("output").join("align");
+        let _ = std::fs::remove_dir_all(&work_dir);
+        std::fs::create_dir_all(&work_dir).unwrap();
+        let bangs = [1000, 4000, 4600, 8000, 11000];
+        // the recording processor writes nothing, so the audio is there already
+        std::fs::write(
+            work_dir.join("angle0.pcm"),
+            recording(&bangs, 0, 15000, 1.0),
+        )
+        .unwrap();
+        std::fs::write(
+            work_dir.join("angle1.pcm"),
+            recording(&bangs, -2000, 15000, 0.5),
+        )
+        .unwrap();
+        let media_paths = vec![PathBuf::from("sideline.mp4"), PathBuf::from("endzone.mp4")];
+        let processor = Arc::new(RecordingProcessor::default());
+
+        let alignments = align_angles(processor.clone(), &media_paths, &work_dir).unwrap();
+        assert_eq!(alignments.len(), 1);
+        assert_eq!(alignments[0].media_path, media_paths[1]);
+        assert_eq!(alignments[0].offset_ms, 2000);
+        assert!(processor.calls().contains(&MediaCall::ExtractAudio {
+            input: media_paths[1].clone(),
+            output: work_dir.join("angle1.pcm"),
+        }));
+        // the extracted audio is removed again
+        assert_eq!(std::fs::read_dir(&work_dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_store_offsets() {
+        let project_dir = Path::new("tests").join("output").join("align_store");
+        let _ = std::fs::remove_dir_all(&project_dir);
+        std::fs::create_dir_all(project_dir.join(".vac")).unwrap();
+        let project_json = serde_json::json!({
+            "version": 2,
+            "media": {},
+            "angles": [{
+                "name": "game1",
+                "angles": [{ "media": "sideline.mp4" }, { "media": "endzone.mp4" }]
+            }]
+        });
+        std::fs::write(
+            project_dir.join(".vac").join("project.json"),
+            project_json.to_string(),
+        )
+        .unwrap();
+        let mut project = Project::load(&project_dir).unwrap();
+        let alignment = |dir: &Path| Alignment {
+            media_path: dir.join("endzone.mp4"),
+            offset_ms: 1200,
+            confidence: 0.8,
+        };
+
+        // videos of the same name in another project are not aligned
+        let other_dir = Path::new("tests").join("output").join("align_other");
+        assert!(store_offsets(
+            &mut project,
+            &other_dir.join("sideline.mp4"),
+            &[alignment(&other_dir)]
+        )
+        .is_err());
+        assert_eq!(
+            project.other_angles(&project_dir.join("sideline.mp4"))[0].1,
+            0
+        );
+
+        store_offsets(
+            &mut project,
+            &project_dir.join("sideline.mp4"),
+            &[alignment(&project_dir)],
+        )
+        .unwrap();
+        assert_eq!(
+            project.other_angles(&project_dir.join("sideline.mp4"))[0].1,
+            1200
+        );
+        std::fs::remove_dir_all(&project_dir).unwrap();
+    }
+}
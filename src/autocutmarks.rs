@@ -0,0 +1,184 @@
+use std::{
+    fs::File,
+    io::{self, BufRead},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{mpsc::Sender, Arc, Mutex},
+};
+
+use crate::Cutmarks;
+
+/// File the AutoCutMarks executable writes the frame numbers of the found cutmarks to
+const SNAPS_FILE_NAME: &str = "snaps.txt";
+
+/// Runs the analysis of AutoCutMarks and returns the cutmarks in ms
+pub(crate) fn run_analysis(
+    acm_exe_path: &Path,
+    videofile: &Path,
+    start_frame: Option<i64>,
+    end_frame: Option<i64>,
+    fps: f32,
+) -> Result<Box<Cutmarks>, String> {
+    let mut cmd = Command::new(acm_exe_path);
+
+    if let Some(start) = start_frame {
+        cmd.arg(format!("-s {}", start));
+    }
+
+    if let Some(end) = end_frame {
+        cmd.arg(format!("-e {}", end));
+    }
+    run(cmd.arg(videofile))?;
+    read_cutmarks_file(fps)
+}
+
+/// Runs the analysis of AutoCutMarks on the data it cached during an earlier analysis
+pub(crate) fn run_analysis_cached(
+    acm_exe_path: &Path,
+    videofile: &Path,
+    fps: f32,
+    sensitivity: Option<f32>,
+) -> Result<Box<Cutmarks>, String> {
+    let mut cmd = Command::new(acm_exe_path);
+    cmd.arg("--mode=use-cached");
+
+    if let Some(sensitivity) = sensitivity {
+        cmd.arg(format!("--sensitivity={}", sensitivity));
+    };
+
+    run(cmd.arg(videofile))?;
+    read_cutmarks_file(fps)
+}
+
+/// Like `run_analysis`, but in a background thread which sends the video with its cutmarks to
+/// `tx`
+pub(crate) fn analyze(
+    acm_exe_path: &Path,
+    videofile: &Path,
+    start_frame: Option<i64>,
+    end_frame: Option<i64>,
+    fps: f32,
+    tx: Sender<(PathBuf, Arc<Mutex<Box<Cutmarks>>>)>,
+) {
+    let acm_exe_path = acm_exe_path.to_path_buf();
+    let videofile = videofile.to_path_buf();
+    std::thread::spawn(move || {
+        match run_analysis(&acm_exe_path, &videofile, start_frame, end_frame, fps) {
+            Ok(cutmarks) => tx
+                .send((videofile, Arc::new(Mutex::new(cutmarks))))
+                .unwrap(),
+            Err(e) => println!("{}", e),
+        }
+    });
+}
+
+/// Like `run_analysis_cached`, but in a background thread which sends the video with its
+/// cutmarks to `tx`
+pub(crate) fn analyze_cached(
+    acm_exe_path: &Path,
+    videofile: &Path,
+    fps: f32,
+    sensitivity: Option<f32>,
+    tx: Sender<(PathBuf, Arc<Mutex<Box<Cutmarks>>>)>,
+) {
+    let acm_exe_path = acm_exe_path.to_path_buf();
+    let videofile = videofile.to_path_buf();
+    std::thread::spawn(move || {
+        match run_analysis_cached(&acm_exe_path, &videofile, fps, sensitivity) {
+            Ok(cutmarks) => tx
+                .send((videofile, Arc::new(Mutex::new(cutmarks))))
+                .unwrap(),
+            Err(e) => println!("{}", e),
+        }
+    });
+}
+
+pub(crate) fn calibrate_near(
+    acm_exe_path: &Path,
+    videofile: &Path,
+    start_frame: i64,
+    threshold: u64,
+) {
+    let status = Command::new(acm_exe_path)
+        .arg("--mode=calibrate-near")
+        .arg(format!("--thresholdNear={}", threshold))
+        .arg(format!("-s {}", start_frame))
+        .arg(videofile)
+        .arg(SNAPS_FILE_NAME)
+        .status()
+        .unwrap();
+
+    assert!(status.success());
+}
+
+pub(crate) fn calibrate_far(
+    acm_exe_path: &Path,
+    videofile: &Path,
+    start_frame: i64,
+    threshold: u64,
+) {
+    let status = Command::new(acm_exe_path)
+        .arg("--mode=calibrate-far")
+        .arg(format!("--thresholdFar={}", threshold))
+        .arg(format!("-s {}", start_frame))
+        .arg(videofile)
+        .arg(SNAPS_FILE_NAME)
+        .status()
+        .unwrap();
+
+    assert!(status.success());
+}
+
+/// Runs AutoCutMarks with the snaps file appended to `cmd`
+fn run(cmd: &mut Command) -> Result<(), String> {
+    let status = cmd
+        .arg(SNAPS_FILE_NAME)
+        .status()
+        .map_err(|e| format!("could not execute AutoCutMarks: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("AutoCutMarks failed with {}", status))
+    }
+}
+
+fn read_cutmarks_file(fps: f32) -> Result<Box<Cutmarks>, String> {
+    let cutmarks_file = File::open(SNAPS_FILE_NAME)
+        .map_err(|e| format!("could not open {}: {}", SNAPS_FILE_NAME, e))?;
+    parse_cutmarks(io::BufReader::new(cutmarks_file), fps)
+}
+
+/// Converts the frame numbers of the snaps file to times in ms
+fn parse_cutmarks(reader: impl BufRead, fps: f32) -> Result<Box<Cutmarks>, String> {
+    let mut cutmarks = Box::new(Cutmarks::new());
+    for line in reader.lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let cutmark: u64 = line
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid frame number \"{}\" in {}", line, SNAPS_FILE_NAME))?;
+        let time = (cutmark as f32 / fps * 1000.0) as i64;
+        cutmarks.insert(time);
+    }
+
+    Ok(cutmarks)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_cutmarks() {
+        let cutmarks = parse_cutmarks("0\n50\n125\n".as_bytes(), 25.0).unwrap();
+        assert_eq!(
+            cutmarks.into_iter().collect::<Vec<i64>>(),
+            vec![0, 2000, 5000]
+        );
+
+        assert!(parse_cutmarks("12\nframe\n".as_bytes(), 25.0).is_err());
+    }
+}
@@ -0,0 +1,711 @@
+//! Subcommands that run without a window or a VLC instance, e.g. on a server:
+//!
+//! ```text
+//! vac cut <project dir> <clips.json> [--mode stream-copy|frame-accurate|smart]
+//! vac concat <clips dir> [<output dir>]
+//! vac analyze <AutoCutMarks exe> <video dir> [--cached] [--sensitivity <value>]
+//! vac export <project dir> <clips.csv|clips.json>
+//! vac thumbnail <video> <time in ms> <image>
+//! vac preview <clip or clips dir> [--format gif|webp] [--fps <n>] [--width <pixels>]
+//! vac align <project dir> [<angle group>]
+//! vac gui [<project dir>]
+//! ```
+//!
+//! `vac <project dir>` and `vac <project dir> --export <file>` keep working as before.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    align, autocutmarks, export,
+    ffmpeg::{self, AnimationFormat, CutMode, CutSettings, FfmpegProcessor},
+    jobs::{JobId, JobQueue, JobStatus},
+    media::MediaProcessor,
+    project::{clip_file_name, format_timecode, Clip, PlayAnnotation, Project},
+    settings::Settings,
+};
+
+const USAGE: &str = "usage:
+  vac cut <project dir> <clips.json> [--mode stream-copy|frame-accurate|smart]
+  vac concat <clips dir> [<output dir>]
+  vac analyze <AutoCutMarks exe> <video dir> [--cached] [--sensitivity <value>]
+  vac export <project dir> <clips.csv|clips.json>
+  vac thumbnail <video> <time in ms> <image>
+  vac preview <clip or clips dir> [--format gif|webp] [--fps <n>] [--width <pixels>]
+  vac align <project dir> [<angle group>]
+  vac gui [<project dir>]";
+
+/// A clip to cut with `vac cut`. The JSON written by `vac export` can be used as input,
+/// additional fields are ignored.
+#[derive(Debug, Deserialize)]
+struct ClipDefinition {
+    /// Path of the video, relative to the project directory or absolute
+    source_video: PathBuf,
+    start_ms: i64,
+    end_ms: Option<i64>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// Runs the subcommand given in `args` and returns the exit code
+pub(crate) fn run(args: &[String]) -> i32 {
+    let command_args: Vec<String> = args.iter().skip(2).cloned().collect();
+    let result = match args.get(1).map(String::as_str) {
+        Some("cut") => cut(command_args),
+        Some("concat") => concat(command_args),
+        Some("analyze") => analyze(command_args),
+        Some("export") => export(command_args),
+        Some("thumbnail") => thumbnail(command_args),
+        Some("preview") => preview(command_args),
+        Some("align") => align(command_args),
+        Some("gui") => {
+            crate::run_with_fltk(command_args.first().map(PathBuf::from));
+            Ok(())
+        }
+        Some("help") | Some("--help") | Some("-h") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => match args.iter().position(|arg| arg == "--export") {
+            // the form of earlier versions: `vac <project dir> --export <file>`
+            Some(2) => export(vec![
+                args[1].clone(),
+                args.get(3).cloned().unwrap_or_default(),
+            ]),
+            Some(_) => Err(Error::Usage),
+            None => {
+                crate::run_with_fltk(args.get(1).map(PathBuf::from));
+                Ok(())
+            }
+        },
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(Error::Usage) => {
+            println!("{}", USAGE);
+            2
+        }
+        Err(Error::Failed(e)) => {
+            println!("{}", e);
+            1
+        }
+    }
+}
+
+enum Error {
+    /// The arguments are invalid
+    Usage,
+    Failed(String),
+}
+
+impl From<String> for Error {
+    fn from(e: String) -> Self {
+        Error::Failed(e)
+    }
+}
+
+/// Removes `--<name> <value>` from `args` and returns the value
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, Error> {
+    match args.iter().position(|arg| arg == name) {
+        Some(i) if i + 1 < args.len() => {
+            let value = args.remove(i + 1);
+            args.remove(i);
+            Ok(Some(value))
+        }
+        Some(_) => Err(Error::Usage),
+        None => Ok(None),
+    }
+}
+
+/// Removes the flag `--<name>` from `args` and returns whether it was given
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    let len = args.len();
+    args.retain(|arg| arg != name);
+    args.len() != len
+}
+
+/// ffmpeg and ffprobe as configured in settings.toml. Problems with them are printed, the
+/// commands fail later with the errors of the tools.
+fn processor() -> Arc<FfmpegProcessor> {
+    let (processor, tool_checks) = FfmpegProcessor::discover(&Settings::load().tools);
+    for check in tool_checks.iter().filter(|check| check.problem.is_some()) {
+        println!("{}", check);
+    }
+    Arc::new(processor)
+}
+
+fn load_project(dir: &Path) -> Result<Project, String> {
+    Project::load(dir).map_err(|e| format!("could not load project {:?}: {}", dir, e))
+}
+
+fn cut(mut args: Vec<String>) -> Result<(), Error> {
+    let mode = match take_option(&mut args, "--mode")? {
+        Some(mode) => Some(mode.parse::<CutMode>()?),
+        None => None,
+    };
+    let (project_dir, definitions_path) = match args.as_slice() {
+        [project_dir, definitions_path] => {
+            (PathBuf::from(project_dir), PathBuf::from(definitions_path))
+        }
+        _ => return Err(Error::Usage),
+    };
+
+    let mut project = load_project(&project_dir)?;
+    let content = std::fs::read_to_string(&definitions_path)
+        .map_err(|e| format!("could not read {:?}: {}", definitions_path, e))?;
+    let definitions: Vec<ClipDefinition> = serde_json::from_str(&content)
+        .map_err(|e| format!("invalid clip definitions in {:?}: {}", definitions_path, e))?;
+
+    let mut settings = project.cut_settings().clone();
+    if let Some(mode) = mode {
+        settings.mode = mode;
+    }
+    let failed = cut_clips(&mut project, definitions, processor(), &settings)?;
+
+    project
+        .save()
+        .map_err(|e| format!("could not save project: {}", e))?;
+    if failed > 0 {
+        return Err(Error::Failed(format!("{} clips could not be cut", failed)));
+    }
+    Ok(())
+}
+
+/// Cuts the clips into the project's `_clips` directory, waits for all of them and adds the
+/// ones that were cut to the project. Returns the number of clips that could not be cut.
+fn cut_clips(
+    project: &mut Project,
+    definitions: Vec<ClipDefinition>,
+    processor: Arc<dyn MediaProcessor>,
+    settings: &CutSettings,
+) -> Result<usize, String> {
+    std::fs::create_dir_all(project.clips_dir())
+        .map_err(|e| format!("could not create {:?}: {}", project.clips_dir(), e))?;
+
+    let mut jobs = JobQueue::new(processor.clone());
+    let mut pending_clips: HashMap<JobId, (PathBuf, i64, Clip)> = HashMap::new();
+    let mut failed = 0;
+    for definition in definitions {
+        let media_path = project.dir().join(&definition.source_video);
+        let end_ms = match definition.end_ms {
+            Some(end_ms) if end_ms > definition.start_ms => end_ms,
+            _ => {
+                println!(
+                    "skipping clip of {:?} at {}: no valid end",
+                    definition.source_video,
+                    format_timecode(definition.start_ms)
+                );
+                failed += 1;
+                continue;
+            }
+        };
+        let tags = match project.find_tags(&definition.tags) {
+            Ok(tags) => tags,
+            Err(e) => {
+                println!("skipping clip of {:?}: {}", definition.source_video, e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        let output =
+            project
+                .clips_dir()
+                .join(clip_file_name(&media_path, definition.start_ms, &tags));
+        let description = format!(
+            "cut {} at {}",
+            media_path.file_name().unwrap_or_default().to_string_lossy(),
+            format_timecode(definition.start_ms)
+        );
+        let job = processor.cut(
+            description,
+            &media_path,
+            definition.start_ms,
+            end_ms,
+            &output,
+            settings,
+        );
+        let clip = Clip {
+            end: Some(end_ms),
+            tags: definition.tags,
+            annotation: PlayAnnotation::default(),
+        };
+        pending_clips.insert(jobs.submit(job), (media_path, definition.start_ms, clip));
+    }
+
+    let count = pending_clips.len();
+    println!("cutting {} clips", count);
+    while jobs.unfinished_count() > 0 {
+        for update in jobs.poll() {
+            match &update.status {
+                JobStatus::Succeeded => {
+                    println!("done: {}", update.description);
+                    if let Some((media_path, start, clip)) = pending_clips.remove(&update.id) {
+                        if let Some(metadata) = project.media_metadata_mut(&media_path) {
+                            metadata.clips.insert(start, clip);
+                        }
+                    }
+                }
+                JobStatus::Failed(e) => {
+                    println!("FAILED: {}: {}", update.description, e);
+                    failed += 1;
+                }
+                JobStatus::Cancelled => {
+                    println!("CANCELLED: {}", update.description);
+                    failed += 1;
+                }
+                JobStatus::Queued | JobStatus::Running(_) => {}
+            }
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    Ok(failed)
+}
+
+fn concat(args: Vec<String>) -> Result<(), Error> {
+    let clips_dir = match args.first() {
+        Some(dir) if args.len() <= 2 => PathBuf::from(dir),
+        _ => return Err(Error::Usage),
+    };
+    let project_dir = clips_dir
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let output_dir = match args.get(1) {
+        Some(dir) => PathBuf::from(dir),
+        None => project_dir.join("_condensed"),
+    };
+
+    // the tags of the project the clips belong to decide which videos are created
+    let project = load_project(&project_dir)?;
+    std::fs::create_dir_all(&output_dir)
+        .map_err(|e| format!("could not create {:?}: {}", output_dir, e))?;
+    let condensed_videos = ffmpeg::concat(
+        processor(),
+        &clips_dir,
+        &output_dir,
+        project.tags(),
+        &project.cut_settings().encoder,
+    )?;
+    for video in condensed_videos.iter() {
+        println!(
+            "{:?}: {} clips, {}",
+            video.path, video.clip_count, video.strategy
+        );
+    }
+    Ok(())
+}
+
+fn analyze(mut args: Vec<String>) -> Result<(), Error> {
+    let cached = take_flag(&mut args, "--cached");
+    let sensitivity = match take_option(&mut args, "--sensitivity")? {
+        Some(value) => Some(
+            value
+                .parse::<f32>()
+                .map_err(|_| format!("invalid sensitivity \"{}\"", value))?,
+        ),
+        None => None,
+    };
+    let (acm_exe_path, video_dir) = match args.as_slice() {
+        [acm_exe_path, video_dir] => (PathBuf::from(acm_exe_path), PathBuf::from(video_dir)),
+        _ => return Err(Error::Usage),
+    };
+
+    let mut project = load_project(&video_dir)?;
+    let processor = processor();
+    let media_paths = crate::list_media(&video_dir)
+        .map_err(|e| format!("could not read {:?}: {}", video_dir, e))?;
+    let mut failed = 0;
+    for media_path in media_paths.iter() {
+        println!("analyzing {:?}", media_path);
+        let result = processor.probe(media_path).and_then(|format| {
+            let fps = format.fps()?;
+            if cached {
+                autocutmarks::run_analysis_cached(&acm_exe_path, media_path, fps, sensitivity)
+            } else {
+                autocutmarks::run_analysis(&acm_exe_path, media_path, None, None, fps)
+            }
+        });
+        match result {
+            Ok(cutmarks) => {
+                println!("found {} cutmarks", cutmarks.len());
+                if let Some(metadata) = project.media_metadata_mut(media_path) {
+                    metadata.cutmarks = Some(cutmarks);
+                }
+                // save after every video, analyzing a folder can take hours
+                project
+                    .save()
+                    .map_err(|e| format!("could not save project: {}", e))?;
+            }
+            Err(e) => {
+                println!("FAILED: {}", e);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(Error::Failed(format!(
+            "{} of {} videos could not be analyzed",
+            failed,
+            media_paths.len()
+        )));
+    }
+    Ok(())
+}
+
+fn export(args: Vec<String>) -> Result<(), Error> {
+    let (project_dir, output_path) = match args.as_slice() {
+        [project_dir, output_path] if !output_path.is_empty() => {
+            (PathBuf::from(project_dir), PathBuf::from(output_path))
+        }
+        _ => return Err(Error::Usage),
+    };
+
+    let mut project = load_project(&project_dir)?;
+    recover_clips(&mut project, processor().as_ref())?;
+    let count = export::export_clips(&project, &output_path)
+        .map_err(|e| format!("export failed: {}", e))?;
+    println!("exported {} clips to {:?}", count, output_path);
+    Ok(())
+}
+
+/// Adds the clips in the clips directory which the project file does not know, like the player
+/// does when it opens a project, and saves the project if clips were recovered
+fn recover_clips(project: &mut Project, processor: &dyn MediaProcessor) -> Result<(), String> {
+    let (changed, problems) = project.recover_clips(&[], |clip_file| {
+        processor.probe(clip_file).map(|format| format.duration_ms)
+    });
+    for problem in problems.iter() {
+        println!("could not recover clip {}", problem);
+    }
+    if changed > 0 {
+        println!("recovered {} clips from the clips directory", changed);
+        project
+            .save()
+            .map_err(|e| format!("could not save project: {}", e))?;
+    }
+    Ok(())
+}
+
+fn thumbnail(args: Vec<String>) -> Result<(), Error> {
+    let (video_path, time_ms, output_path) = match args.as_slice() {
+        [video_path, time_ms, output_path] => match time_ms.parse::<i64>() {
+            Ok(time_ms) if time_ms >= 0 => (
+                PathBuf::from(video_path),
+                time_ms,
+                PathBuf::from(output_path),
+            ),
+            _ => return Err(Error::Failed(format!("invalid time \"{}\"", time_ms))),
+        },
+        _ => return Err(Error::Usage),
+    };
+
+    let processor = processor();
+    let description = format!(
+        "thumbnail of {:?} at {}",
+        video_path,
+        format_timecode(time_ms)
+    );
+    let job = processor.thumbnail(description, &video_path, time_ms, &output_path);
+    let mut jobs = JobQueue::new(processor);
+    jobs.submit(job);
+    for update in jobs.wait_all() {
+        match update.status {
+            JobStatus::Failed(e) => {
+                return Err(Error::Failed(format!(
+                    "FAILED: {}: {}",
+                    update.description, e
+                )))
+            }
+            JobStatus::Cancelled => {
+                return Err(Error::Failed(format!("CANCELLED: {}", update.description)))
+            }
+            _ => {}
+        }
+    }
+    println!("saved {:?}", output_path);
+    Ok(())
+}
+
+fn preview(mut args: Vec<String>) -> Result<(), Error> {
+    let format = match take_option(&mut args, "--format")? {
+        Some(format) => Some(format.parse::<AnimationFormat>()?),
+        None => None,
+    };
+    let fps = match take_option(&mut args, "--fps")? {
+        Some(value) => match value.parse::<f64>() {
+            Ok(fps) if fps > 0.0 => Some(fps),
+            _ => return Err(Error::Failed(format!("invalid frame rate \"{}\"", value))),
+        },
+        None => None,
+    };
+    let width = match take_option(&mut args, "--width")? {
+        Some(value) => match value.parse::<u32>() {
+            Ok(width) if width > 0 => Some(width),
+            _ => return Err(Error::Failed(format!("invalid width \"{}\"", value))),
+        },
+        None => None,
+    };
+    let input_path = match args.as_slice() {
+        [input_path] => PathBuf::from(input_path),
+        _ => return Err(Error::Usage),
+    };
+
+    let (clips_dir, clip_paths) = if input_path.is_dir() {
+        let clip_paths = crate::list_media(&input_path)
+            .map_err(|e| format!("could not read {:?}: {}", input_path, e))?;
+        (input_path, clip_paths)
+    } else {
+        let clips_dir = input_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        (clips_dir, vec![input_path])
+    };
+    // the previews are written next to the clips directory, with the settings of its project
+    let project_dir = clips_dir
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let project = load_project(&project_dir)?;
+    let mut settings = project.preview_settings().clone();
+    if let Some(format) = format {
+        settings.format = format;
+    }
+    if let Some(fps) = fps {
+        settings.fps = fps;
+    }
+    if let Some(width) = width {
+        settings.width = width;
+    }
+    let previews_dir = project.previews_dir();
+    std::fs::create_dir_all(&previews_dir)
+        .map_err(|e| format!("could not create {:?}: {}", previews_dir, e))?;
+
+    let processor = processor();
+    let mut jobs = JobQueue::new(processor.clone());
+    let mut failed = 0;
+    for clip_path in clip_paths.iter() {
+        let duration_ms = match processor.probe(clip_path) {
+            Ok(format) => format.duration_ms,
+            Err(e) => {
+                println!("skipping {:?}: {}", clip_path, e);
+                failed += 1;
+                continue;
+            }
+        };
+        let stem = clip_path.file_stem().unwrap_or_default().to_string_lossy();
+        for job in ffmpeg::preview_jobs(
+            processor.as_ref(),
+            clip_path,
+            0,
+            duration_ms,
+            &settings,
+            &previews_dir,
+            &stem,
+        ) {
+            jobs.submit(job);
+        }
+    }
+
+    for update in jobs.wait_all() {
+        match update.status {
+            JobStatus::Succeeded => println!("done: {}", update.description),
+            JobStatus::Failed(e) => {
+                println!("FAILED: {}: {}", update.description, e);
+                failed += 1;
+            }
+            JobStatus::Cancelled => {
+                println!("CANCELLED: {}", update.description);
+                failed += 1;
+            }
+            JobStatus::Queued | JobStatus::Running(_) => {}
+        }
+    }
+    if failed > 0 {
+        return Err(Error::Failed(format!(
+            "{} previews could not be exported",
+            failed
+        )));
+    }
+    println!("previews written to {:?}", previews_dir);
+    Ok(())
+}
+
+fn align(args: Vec<String>) -> Result<(), Error> {
+    let (project_dir, group_name) = match args.as_slice() {
+        [project_dir] => (PathBuf::from(project_dir), None),
+        [project_dir, group_name] => (PathBuf::from(project_dir), Some(group_name.as_str())),
+        _ => return Err(Error::Usage),
+    };
+
+    let mut project = load_project(&project_dir)?;
+    let groups: Vec<(String, Vec<PathBuf>)> = project
+        .angle_groups()
+        .iter()
+        .filter(|group| group_name.is_none() || group_name == Some(group.name.as_str()))
+        .map(|group| (group.name.clone(), project.angle_media_paths(group)))
+        .collect();
+    if groups.is_empty() {
+        return Err(Error::Failed(match group_name {
+            Some(name) => format!("there is no angle group \"{}\"", name),
+            None => format!("{:?} has no angle groups", project_dir),
+        }));
+    }
+
+    let processor = processor();
+    let mut failed = 0;
+    for (name, media_paths) in groups.iter() {
+        println!("aligning the angles of {}", name);
+        match align::align_angles(processor.clone(), media_paths, &project.work_dir()) {
+            Ok(alignments) => {
+                for alignment in alignments.iter() {
+                    println!("{}", alignment);
+                }
+                align::store_offsets(&mut project, &media_paths[0], &alignments)?;
+                project
+                    .save()
+                    .map_err(|e| format!("could not save project: {}", e))?;
+            }
+            Err(e) => {
+                println!("FAILED: {}", e);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(Error::Failed(format!(
+            "{} of {} angle groups could not be aligned",
+            failed,
+            groups.len()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_options() {
+        let mut cut_args = args(&["project", "--mode", "smart", "clips.json"]);
+        assert_eq!(
+            take_option(&mut cut_args, "--mode").ok().flatten(),
+            Some("smart".to_owned())
+        );
+        assert_eq!(cut_args, args(&["project", "clips.json"]));
+
+        let mut analyze_args = args(&["acm.exe", "videos", "--cached"]);
+        assert!(take_flag(&mut analyze_args, "--cached"));
+        assert!(!take_flag(&mut analyze_args, "--cached"));
+        assert!(take_option(&mut args(&["videos", "--sensitivity"]), "--sensitivity").is_err());
+    }
+
+    #[test]
+    fn test_clip_definitions_from_export() {
+        let definitions: Vec<ClipDefinition> = serde_json::from_str(
+            r#"[
+                {"source_video": "game.mp4", "start_ms": 1000, "end_ms": 5000,
+                 "start_timecode": "0:00:01.000", "tags": ["Offense"], "note": ""},
+                {"source_video": "game.mp4", "start_ms": 9000, "end_ms": null}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(definitions.len(), 2);
+        assert_eq!(definitions[0].tags, vec!["Offense".to_owned()]);
+        assert_eq!(definitions[1].end_ms, None);
+        assert!(definitions[1].tags.is_empty());
+    }
+
+    #[test]
+    fn test_export_recovered_clips() {
+        let project_dir = Path::new("tests").join("output").join("cli_export_recover");
+        let _ = std::fs::remove_dir_all(&project_dir);
+        let mut project = Project::load(&project_dir).unwrap();
+        std::fs::create_dir_all(project.clips_dir()).unwrap();
+        let clip_path = project.clips_dir().join("game.mp4_00004000Def.mp4");
+        std::fs::write(&clip_path, "").unwrap();
+        let mut processor = crate::media::RecordingProcessor::default();
+        processor.formats.insert(
+            clip_path,
+            ffmpeg::MediaFormat {
+                duration_ms: 2000,
+                ..Default::default()
+            },
+        );
+
+        recover_clips(&mut project, &processor).unwrap();
+        let export_path = project_dir.join("clips.json");
+        assert_eq!(export::export_clips(&project, &export_path), Ok(1));
+        let definitions: Vec<ClipDefinition> =
+            serde_json::from_str(&std::fs::read_to_string(&export_path).unwrap()).unwrap();
+        assert_eq!(definitions[0].start_ms, 4000);
+        assert_eq!(definitions[0].end_ms, Some(6000));
+        assert_eq!(definitions[0].tags, vec!["Defense".to_owned()]);
+        // the recovered clip is saved in the project
+        let project = Project::load(&project_dir).unwrap();
+        assert!(project
+            .media_metadata(&project_dir.join("game.mp4"))
+            .is_some());
+    }
+
+    #[test]
+    fn test_export_then_cut() {
+        // a relative project directory, as given on the command line
+        let project_dir = Path::new("tests").join("output").join("cli_export_cut");
+        let _ = std::fs::remove_dir_all(&project_dir);
+        std::fs::create_dir_all(&project_dir).unwrap();
+        let media_path = project_dir.join("game.mp4");
+        let mut project = Project::load(&project_dir).unwrap();
+        project
+            .media_metadata_mut(&media_path)
+            .unwrap()
+            .clips
+            .insert(
+                3000,
+                Clip {
+                    end: Some(8000),
+                    tags: vec!["Offense".to_owned()],
+                    annotation: PlayAnnotation::default(),
+                },
+            );
+        let export_path = project_dir.join("clips.json");
+        export::export_clips(&project, &export_path).unwrap();
+
+        let definitions: Vec<ClipDefinition> =
+            serde_json::from_str(&std::fs::read_to_string(&export_path).unwrap()).unwrap();
+        let mut project = Project::load(&project_dir).unwrap();
+        let processor = Arc::new(crate::media::RecordingProcessor::default());
+        let settings = project.cut_settings().clone();
+        let failed = cut_clips(&mut project, definitions, processor.clone(), &settings).unwrap();
+        assert_eq!(failed, 0);
+        // the video is found in the project directory, not in `<project dir>/<project dir>`
+        match &processor.calls()[..] {
+            [crate::media::MediaCall::Cut {
+                input,
+                start_ms: 3000,
+                end_ms: 8000,
+                ..
+            }, ..] => assert_eq!(input, &media_path),
+            calls => panic!("unexpected calls {:?}", calls),
+        }
+        assert_eq!(
+            project.media_metadata(&media_path).unwrap().clips[&3000].tags,
+            vec!["Offense".to_owned()]
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use std::io::Write;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::project::{format_timecode, Project};
+
+/// One clip of a project as it is exported
+#[derive(Debug, Serialize)]
+struct ClipRow {
+    source_video: String,
+    start_ms: i64,
+    end_ms: Option<i64>,
+    start_timecode: String,
+    end_timecode: Option<String>,
+    tags: Vec<String>,
+    down: Option<u8>,
+    distance: Option<u32>,
+    field_position: String,
+    formation: String,
+    play_call: String,
+    result_yards: Option<i32>,
+    note: String,
+    /// Path of the clip in the project's clips directory, if it has been cut already
+    clip_file: Option<String>,
+}
+
+const CSV_HEADER: &[&str] = &[
+    "source_video",
+    "start_ms",
+    "end_ms",
+    "start_timecode",
+    "end_timecode",
+    "tags",
+    "down",
+    "distance",
+    "field_position",
+    "formation",
+    "play_call",
+    "result_yards",
+    "note",
+    "clip_file",
+];
+
+/// Separates several tags of a clip within one CSV field
+const CSV_TAG_SEPARATOR: &str = ";";
+
+fn clip_rows(project: &Project) -> Vec<ClipRow> {
+    let mut rows = Vec::new();
+    for (media_path, metadata) in project.media() {
+        for (start, clip) in metadata.clips.iter() {
+            let clip_file_path = project.clip_file_path(&media_path, *start, clip);
+            let annotation = &clip.annotation;
+            rows.push(ClipRow {
+                // relative to the project, so `vac cut` finds the video wherever the project is
+                source_video: Project::media_key(&media_path).unwrap_or_default(),
+                start_ms: *start,
+                end_ms: clip.end,
+                start_timecode: format_timecode(*start),
+                end_timecode: clip.end.map(format_timecode),
+                tags: clip.tags.clone(),
+                down: annotation.down,
+                distance: annotation.distance,
+                field_position: annotation.field_position.clone(),
+                formation: annotation.formation.clone(),
+                play_call: annotation.play_call.clone(),
+                result_yards: annotation.result_yards,
+                note: annotation.note.clone(),
+                clip_file: if clip_file_path.exists() {
+                    Some(clip_file_path.to_string_lossy().into_owned())
+                } else {
+                    None
+                },
+            });
+        }
+    }
+    rows
+}
+
+/// Quotes a CSV field if it contains a separator, a quote or a line break
+fn csv_field(value: &str) -> String {
+    if value.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+fn optional_field<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn write_csv(rows: &[ClipRow], writer: &mut impl Write) -> std::io::Result<()> {
+    writeln!(writer, "{}", CSV_HEADER.join(","))?;
+    for row in rows {
+        let fields = [
+            row.source_video.clone(),
+            row.start_ms.to_string(),
+            optional_field(&row.end_ms),
+            row.start_timecode.clone(),
+            optional_field(&row.end_timecode),
+            row.tags.join(CSV_TAG_SEPARATOR),
+            optional_field(&row.down),
+            optional_field(&row.distance),
+            row.field_position.clone(),
+            row.formation.clone(),
+            row.play_call.clone(),
+            optional_field(&row.result_yards),
+            row.note.clone(),
+            optional_field(&row.clip_file),
+        ];
+        let line: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        writeln!(writer, "{}", line.join(","))?;
+    }
+    Ok(())
+}
+
+/// Writes all clips of the project to `output_path`, as CSV or JSON depending on its extension.
+/// Returns the number of exported clips.
+pub(crate) fn export_clips(project: &Project, output_path: &Path) -> Result<usize, String> {
+    let rows = clip_rows(project);
+    let extension = output_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase());
+
+    let mut file = std::io::BufWriter::new(
+        std::fs::File::create(output_path).map_err(|e| format!("{:?}: {}", output_path, e))?,
+    );
+    match extension.as_deref() {
+        Some("csv") => write_csv(&rows, &mut file).map_err(|e| e.to_string())?,
+        Some("json") => {
+            serde_json::to_writer_pretty(&mut file, &rows).map_err(|e| e.to_string())?
+        }
+        _ => {
+            return Err(format!(
+                "{:?}: unknown export format, use a .csv or .json file",
+                output_path
+            ))
+        }
+    }
+    file.flush().map_err(|e| e.to_string())?;
+
+    Ok(rows.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::project::{Clip, PlayAnnotation};
+
+    #[test]
+    fn test_export_csv() {
+        let project_dir = Path::new("tests").join("output").join("export_csv");
+        let _ = std::fs::remove_dir_all(&project_dir);
+        std::fs::create_dir_all(&project_dir).unwrap();
+
+        let mut project = Project::load(&project_dir).unwrap();
+        let media_path = project_dir.join("game.mp4");
+        let clip = Clip {
+            end: Some(9500),
+            tags: vec!["Offense".to_owned(), "Defense".to_owned()],
+            annotation: PlayAnnotation {
+                down: Some(3),
+                note: "QB \"scrambles\", throws away".to_owned(),
+                ..Default::default()
+            },
+        };
+        let clip_file_path = project.clip_file_path(&media_path, 3601, &clip);
+        std::fs::create_dir_all(project.clips_dir()).unwrap();
+        std::fs::write(&clip_file_path, b"").unwrap();
+        let metadata = project.media_metadata_mut(&media_path).unwrap();
+        metadata.clips.insert(3601, clip);
+        metadata.clips.insert(20000, Clip::default());
+
+        let mut csv = Vec::new();
+        write_csv(&clip_rows(&project), &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[1],
+            format!(
+                "game.mp4,3601,9500,0:00:03.601,0:00:09.500,Offense;Defense,3,,,,,,\"QB \"\"scrambles\"\", throws away\",{}",
+                clip_file_path.to_string_lossy()
+            )
+        );
+        assert!(lines[2].ends_with(",20000,,0:00:20.000,,,,,,,,,,"));
+    }
+}
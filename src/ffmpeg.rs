@@ -1,85 +1,1653 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
+use std::ffi::OsString;
 
-use std::io::Write;
-use std::path::Path;
-use std::process::Command;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::{mpsc::channel, Arc};
+use std::time::Duration;
 
-pub fn concat(input_dir_path: &Path, output_dir_path: &Path) -> Result<(), String> {
-    let index_file_path = Path::new("index.txt");
-    let mut sorted_offense = BTreeSet::new();
-    let mut sorted_defense = BTreeSet::new();
+use serde::{Deserialize, Serialize};
+
+use crate::jobs::{Job, JobQueue, JobStatus, JobStep, TempFile};
+use crate::media::MediaProcessor;
+use crate::project::{clip_tag_suffixes, file_name_safe, ClipTag, ReelClipTexts, ReelOptions};
+use crate::settings::ToolSettings;
+
+/// How clips are cut out of a video
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub(crate) enum CutMode {
+    /// Copies the streams without re-encoding. Fast and lossless, but the clip starts at the
+    /// keyframe before the loop start.
+    StreamCopy,
+    /// Re-encodes the whole clip, so it starts exactly at the loop start
+    FrameAccurate,
+    /// Re-encodes only the part before the first keyframe of the clip and copies the rest
+    Smart,
+}
+
+impl std::str::FromStr for CutMode {
+    type Err = String;
+
+    /// Parses the names used on the command line, e.g. `frame-accurate`
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "stream-copy" => Ok(CutMode::StreamCopy),
+            "frame-accurate" => Ok(CutMode::FrameAccurate),
+            "smart" => Ok(CutMode::Smart),
+            _ => Err(format!(
+                "unknown cut mode \"{}\", expected stream-copy, frame-accurate or smart",
+                name
+            )),
+        }
+    }
+}
+
+impl CutMode {
+    pub(crate) const ALL: [CutMode; 3] =
+        [CutMode::StreamCopy, CutMode::FrameAccurate, CutMode::Smart];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            CutMode::StreamCopy => "Stream copy",
+            CutMode::FrameAccurate => "Frame accurate",
+            CutMode::Smart => "Smart",
+        }
+    }
+}
+
+// `#[default]` on enum variants needs a newer Rust than the one VAC supports
+#[allow(clippy::derivable_impls)]
+impl Default for CutMode {
+    fn default() -> Self {
+        CutMode::StreamCopy
+    }
+}
+
+/// Encoder settings for re-encoding clips
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct EncoderPreset {
+    /// ffmpeg video encoder, e.g. "libx264" or "libx265"
+    pub(crate) video_codec: String,
+    /// Constant rate factor, lower is better quality
+    pub(crate) crf: u8,
+    /// Speed preset of the encoder, e.g. "veryfast" or "slow"
+    pub(crate) preset: String,
+    /// ffmpeg audio encoder or "copy"
+    pub(crate) audio_codec: String,
+    /// e.g. "192k", ignored if the audio is copied
+    pub(crate) audio_bitrate: String,
+}
+
+impl Default for EncoderPreset {
+    fn default() -> Self {
+        EncoderPreset {
+            video_codec: "libx264".to_owned(),
+            crf: 18,
+            preset: "veryfast".to_owned(),
+            audio_codec: "aac".to_owned(),
+            audio_bitrate: "192k".to_owned(),
+        }
+    }
+}
+
+impl EncoderPreset {
+    fn codec_args(&self, video_codec: &str) -> Vec<OsString> {
+        let mut args: Vec<OsString> = vec![
+            "-c:v".into(),
+            video_codec.into(),
+            "-crf".into(),
+            format!("{}", self.crf).into(),
+            "-preset".into(),
+            self.preset.as_str().into(),
+            "-c:a".into(),
+            self.audio_codec.as_str().into(),
+        ];
+        if self.audio_codec != "copy" && !self.audio_bitrate.is_empty() {
+            args.push("-b:a".into());
+            args.push(self.audio_bitrate.as_str().into());
+        }
+        args
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct CutSettings {
+    pub(crate) mode: CutMode,
+    pub(crate) encoder: EncoderPreset,
+}
+
+/// File format of animated previews
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub(crate) enum AnimationFormat {
+    Gif,
+    WebP,
+}
+
+impl std::str::FromStr for AnimationFormat {
+    type Err = String;
+
+    /// Parses the names used on the command line, e.g. `webp`
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "gif" => Ok(AnimationFormat::Gif),
+            "webp" => Ok(AnimationFormat::WebP),
+            _ => Err(format!(
+                "unknown animation format \"{}\", expected gif or webp",
+                name
+            )),
+        }
+    }
+}
+
+impl AnimationFormat {
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            AnimationFormat::Gif => "gif",
+            AnimationFormat::WebP => "webp",
+        }
+    }
+}
+
+// `#[default]` on enum variants needs a newer Rust than the one VAC supports
+#[allow(clippy::derivable_impls)]
+impl Default for AnimationFormat {
+    fn default() -> Self {
+        AnimationFormat::Gif
+    }
+}
+
+/// How the previews of clips look, see `preview_jobs`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct PreviewSettings {
+    pub(crate) format: AnimationFormat,
+    /// Frames per second of the animation
+    pub(crate) fps: f64,
+    /// Width of the animation in pixels, the height keeps the aspect ratio
+    pub(crate) width: u32,
+    /// Number of frames across the contact sheet
+    pub(crate) sheet_columns: u32,
+    /// Number of frames down the contact sheet
+    pub(crate) sheet_rows: u32,
+    /// Width of the whole contact sheet in pixels
+    pub(crate) sheet_width: u32,
+}
+
+impl Default for PreviewSettings {
+    fn default() -> Self {
+        PreviewSettings {
+            format: AnimationFormat::Gif,
+            fps: 10.0,
+            width: 480,
+            sheet_columns: 4,
+            sheet_rows: 3,
+            sheet_width: 1600,
+        }
+    }
+}
+
+fn seconds(ms: i64) -> String {
+    format!("{:.3}", ms as f64 / 1000.0)
+}
+
+/// Arguments for ffmpeg to cut `duration_ms` starting at `start_ms` out of `input_path`
+/// with the given codec arguments. The output file has to be appended.
+fn cut_args(
+    input_path: &Path,
+    start_ms: i64,
+    duration_ms: i64,
+    codec_args: Vec<OsString>,
+) -> Vec<OsString> {
+    cut_args_in_seconds(
+        input_path,
+        seconds(start_ms),
+        seconds(duration_ms),
+        codec_args,
+    )
+}
+
+/// Like `cut_args`, with the start and the duration as passed to ffmpeg, e.g. `12.345600`
+fn cut_args_in_seconds(
+    input_path: &Path,
+    start: String,
+    duration: String,
+    codec_args: Vec<OsString>,
+) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec![
+        "-ss".into(),
+        start.into(),
+        "-i".into(),
+        input_path.into(),
+        "-t".into(),
+        duration.into(),
+    ];
+    args.extend(codec_args);
+    args
+}
+
+fn copy_args() -> Vec<OsString> {
+    vec!["-c".into(), "copy".into()]
+}
+
+/// Creates the job which cuts `start_ms` to `end_ms` out of `input_path` into `output_path`.
+/// In smart mode, the job probes the input with ffprobe to find the keyframes when it runs.
+fn cut_job(
+    ffprobe_path: &Path,
+    description: String,
+    input_path: &Path,
+    start_ms: i64,
+    end_ms: i64,
+    output_path: &Path,
+    settings: &CutSettings,
+) -> Job {
+    let duration_ms = end_ms - start_ms;
+    let single_step = |codec_args| {
+        Job::single(
+            description.clone(),
+            JobStep {
+                args: cut_args(input_path, start_ms, duration_ms, codec_args),
+                output: output_path.to_path_buf(),
+                duration_ms,
+            },
+        )
+    };
+    let frame_accurate = || single_step(settings.encoder.codec_args(&settings.encoder.video_codec));
+
+    match settings.mode {
+        CutMode::StreamCopy => single_step(copy_args()),
+        CutMode::FrameAccurate => frame_accurate(),
+        CutMode::Smart => {
+            let mut job = frame_accurate();
+            let fallback = frame_accurate();
+            let copy = single_step(copy_args());
+            let ffprobe_path = ffprobe_path.to_path_buf();
+            let input_path = input_path.to_path_buf();
+            let output_path = output_path.to_path_buf();
+            let encoder = settings.encoder.clone();
+            job.prepare = Some(Box::new(move || {
+                match smart_cut_job(
+                    &ffprobe_path,
+                    &description,
+                    &input_path,
+                    start_ms,
+                    end_ms,
+                    &output_path,
+                    &encoder,
+                ) {
+                    Ok(Some(job)) => job,
+                    Ok(None) => copy,
+                    Err(e) => {
+                        println!("smart cut not possible, re-encoding the whole clip: {}", e);
+                        fallback
+                    }
+                }
+            }));
+            job
+        }
+    }
+}
+
+/// Creates the job which saves the frame at `time_ms` of `input_path` as an image, e.g. a `.jpg`
+fn thumbnail_job(description: String, input_path: &Path, time_ms: i64, output_path: &Path) -> Job {
+    Job::single(
+        description,
+        JobStep {
+            args: vec![
+                "-ss".into(),
+                seconds(time_ms).into(),
+                "-i".into(),
+                input_path.into(),
+                "-frames:v".into(),
+                "1".into(),
+                "-q:v".into(),
+                "2".into(),
+            ],
+            output: output_path.to_path_buf(),
+            duration_ms: 0,
+        },
+    )
+}
+
+/// Creates the job which turns `start_ms` to `end_ms` of `input_path` into an animated GIF or
+/// WebP. A GIF gets a palette made from the clip itself, so its 256 colors fit field and jerseys.
+fn animation_job(
+    description: String,
+    input_path: &Path,
+    start_ms: i64,
+    end_ms: i64,
+    settings: &PreviewSettings,
+    output_path: &Path,
+) -> Job {
+    let scale = format!(
+        "fps={},scale={}:-1:flags=lanczos",
+        settings.fps, settings.width
+    );
+    let codec_args: Vec<OsString> = match settings.format {
+        AnimationFormat::Gif => vec![
+            "-vf".into(),
+            format!("{},split[a][b];[a]palettegen[p];[b][p]paletteuse", scale).into(),
+        ],
+        AnimationFormat::WebP => vec![
+            "-vf".into(),
+            scale.into(),
+            "-c:v".into(),
+            "libwebp".into(),
+            "-quality".into(),
+            "75".into(),
+        ],
+    };
+    let mut args = cut_args(input_path, start_ms, end_ms - start_ms, codec_args);
+    args.extend(vec!["-loop".into(), "0".into(), "-an".into()]);
+
+    let mut job = Job::single(
+        description,
+        JobStep {
+            args,
+            output: output_path.to_path_buf(),
+            duration_ms: end_ms - start_ms,
+        },
+    );
+    job.overwrite = true;
+    job
+}
+
+/// Creates the job which writes an image with a grid of frames taken evenly across `start_ms` to
+/// `end_ms` of `input_path`
+fn contact_sheet_job(
+    description: String,
+    input_path: &Path,
+    start_ms: i64,
+    end_ms: i64,
+    settings: &PreviewSettings,
+    output_path: &Path,
+) -> Job {
+    let duration_ms = (end_ms - start_ms).max(1);
+    let columns = settings.sheet_columns.max(1);
+    let rows = settings.sheet_rows.max(1);
+    let fps = (columns * rows) as f64 * 1000.0 / duration_ms as f64;
+    let filter = format!(
+        "fps={:.6},scale={}:-1,tile={}x{}",
+        fps,
+        settings.sheet_width / columns,
+        columns,
+        rows
+    );
+    let codec_args: Vec<OsString> = vec![
+        "-vf".into(),
+        filter.into(),
+        "-frames:v".into(),
+        "1".into(),
+        "-q:v".into(),
+        "2".into(),
+    ];
+
+    let mut job = Job::single(
+        description,
+        JobStep {
+            args: cut_args(input_path, start_ms, duration_ms, codec_args),
+            output: output_path.to_path_buf(),
+            duration_ms,
+        },
+    );
+    job.overwrite = true;
+    job
+}
+
+/// Creates the job which writes the first `duration_ms` of the audio of `input_path` as raw mono
+/// PCM, signed 16 bit little endian. The output is a temporary file, so it is overwritten.
+fn extract_audio_job(
+    description: String,
+    input_path: &Path,
+    duration_ms: i64,
+    sample_rate: u32,
+    output_path: &Path,
+) -> Job {
+    let mut job = Job::single(
+        description,
+        JobStep {
+            args: vec![
+                "-t".into(),
+                seconds(duration_ms).into(),
+                "-i".into(),
+                input_path.into(),
+                "-vn".into(),
+                "-ac".into(),
+                "1".into(),
+                "-ar".into(),
+                sample_rate.to_string().into(),
+                "-f".into(),
+                "s16le".into(),
+            ],
+            output: output_path.to_path_buf(),
+            duration_ms,
+        },
+    );
+    job.overwrite = true;
+    job
+}
+
+/// Returns `None` if the clip starts at a keyframe, so the streams can simply be copied
+fn smart_cut_job(
+    ffprobe_path: &Path,
+    description: &str,
+    input_path: &Path,
+    start_ms: i64,
+    end_ms: i64,
+    output_path: &Path,
+    encoder: &EncoderPreset,
+) -> Result<Option<Job>, String> {
+    let keyframe = match first_keyframe(ffprobe_path, input_path, start_ms, end_ms)? {
+        Some(keyframe) => keyframe,
+        None => return Err("no keyframe within the clip".to_owned()),
+    };
+    let keyframe_ms = keyframe.time_ms();
+    if keyframe_ms <= start_ms + 1 {
+        return Ok(None);
+    }
+
+    // The head has to be encoded with the codec of the input, otherwise it cannot be
+    // concatenated with the copied tail.
+    let format = probe_media_format(ffprobe_path, input_path)?;
+    let video_codec = match format.video_codec.as_str() {
+        "h264" => "libx264",
+        "hevc" => "libx265",
+        other => return Err(format!("cannot re-encode video codec \"{}\"", other)),
+    };
+    let mut head_codec_args = encoder.codec_args(video_codec);
+    if !format.pix_fmt.is_empty() {
+        head_codec_args.push("-pix_fmt".into());
+        head_codec_args.push(format.pix_fmt.as_str().into());
+    }
+    // the tail keeps its audio, so the head must not change the audio codec
+    if let Some(i) = head_codec_args.iter().position(|arg| arg == "-c:a") {
+        head_codec_args.truncate(i);
+    }
+    head_codec_args.push("-c:a".into());
+    head_codec_args.push("copy".into());
+
+    let part_path = |part: &str| {
+        let mut file_name = output_path.file_stem().unwrap().to_os_string();
+        file_name.push(format!(".{}.", part));
+        file_name.push(output_path.extension().unwrap_or_default());
+        output_path.with_file_name(file_name)
+    };
+    let head_path = part_path("head");
+    let tail_path = part_path("tail");
+    let index_file = index_file(
+        output_path.with_extension("index.txt"),
+        &[&head_path, &tail_path],
+    );
+
+    // The head ends right before the keyframe and the tail is copied from exactly the time
+    // ffprobe reported for it. With a rounded time, ffmpeg could start copying at the keyframe
+    // before it, which would repeat a whole group of pictures.
+    let head_duration_ms = keyframe_ms - start_ms;
+    let head_duration = format!("{:.6}", keyframe.seconds - start_ms as f64 / 1000.0);
+    let tail_duration_ms = end_ms - keyframe_ms;
+    Ok(Some(Job {
+        description: description.to_owned(),
+        steps: vec![
+            JobStep {
+                args: cut_args_in_seconds(
+                    input_path,
+                    seconds(start_ms),
+                    head_duration,
+                    head_codec_args,
+                ),
+                output: head_path,
+                duration_ms: head_duration_ms,
+            },
+            JobStep {
+                args: cut_args_in_seconds(
+                    input_path,
+                    keyframe.pts_time,
+                    seconds(tail_duration_ms),
+                    copy_args(),
+                ),
+                output: tail_path,
+                duration_ms: tail_duration_ms,
+            },
+            JobStep {
+                args: vec![
+                    "-f".into(),
+                    "concat".into(),
+                    "-safe".into(),
+                    "0".into(),
+                    "-i".into(),
+                    index_file.path.as_os_str().to_owned(),
+                    "-c".into(),
+                    "copy".into(),
+                ],
+                output: output_path.to_path_buf(),
+                // only takes a fraction of the time of the other steps
+                duration_ms: 0,
+            },
+        ],
+        temp_files: vec![index_file],
+        overwrite: false,
+        prepare: None,
+    }))
+}
+
+/// Index file listing `files` for ffmpeg's concat demuxer
+fn index_file(path: PathBuf, files: &[&Path]) -> TempFile {
+    let content = files
+        .iter()
+        .map(|file_path| {
+            // a quote is written as '\'' within the quoted path
+            let quoted = file_path.to_string_lossy().replace('\'', "'\\''");
+            format!("file '{}'\n", quoted)
+        })
+        .collect();
+    TempFile { path, content }
+}
+
+/// Runs ffprobe on `input_path` and returns its output
+fn ffprobe(ffprobe_path: &Path, input_path: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(ffprobe_path)
+        .arg("-v")
+        .arg("error")
+        .args(args)
+        .arg(input_path)
+        .output()
+        .map_err(|e| format!("could not execute ffprobe {:?}: {}", ffprobe_path, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "error from ffprobe: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct AudioFormat {
+    pub(crate) codec_name: String,
+    pub(crate) sample_rate: String,
+    pub(crate) channels: String,
+}
+
+/// Format of the first video and audio stream of a file, as far as it matters for
+/// concatenating files without re-encoding
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct MediaFormat {
+    pub(crate) video_codec: String,
+    pub(crate) pix_fmt: String,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    /// e.g. `30000/1001`
+    pub(crate) frame_rate: String,
+    /// `None` if there is no audio stream
+    pub(crate) audio: Option<AudioFormat>,
+    pub(crate) duration_ms: i64,
+}
+
+impl MediaFormat {
+    /// Frames per second of the video stream
+    pub(crate) fn fps(&self) -> Result<f32, String> {
+        parse_frame_rate(&self.frame_rate)
+            .ok_or_else(|| format!("invalid frame rate \"{}\"", self.frame_rate))
+    }
+}
+
+fn probe_media_format(ffprobe_path: &Path, input_path: &Path) -> Result<MediaFormat, String> {
+    let output = ffprobe(
+        ffprobe_path,
+        input_path,
+        &[
+            "-show_entries",
+            "stream=codec_type,codec_name,pix_fmt,width,height,r_frame_rate,sample_rate,channels\
+             :format=duration",
+        ],
+    )?;
+    let format = parse_media_format(&output);
+    if format.video_codec.is_empty() {
+        return Err(format!("{:?} has no video stream", input_path));
+    }
+    Ok(format)
+}
+
+/// Parses the `[STREAM]` and `[FORMAT]` sections of ffprobe's default output format
+fn parse_media_format(output: &str) -> MediaFormat {
+    let mut format = MediaFormat::default();
+    let mut stream: HashMap<&str, &str> = HashMap::new();
+    for line in output.lines().map(str::trim) {
+        if line == "[/STREAM]" {
+            let value = |key: &str| stream.get(key).copied().unwrap_or_default().to_owned();
+            match stream.get("codec_type") {
+                Some(&"video") if format.video_codec.is_empty() => {
+                    format.video_codec = value("codec_name");
+                    format.pix_fmt = value("pix_fmt");
+                    format.width = value("width").parse().unwrap_or_default();
+                    format.height = value("height").parse().unwrap_or_default();
+                    format.frame_rate = value("r_frame_rate");
+                }
+                Some(&"audio") if format.audio.is_none() => {
+                    format.audio = Some(AudioFormat {
+                        codec_name: value("codec_name"),
+                        sample_rate: value("sample_rate"),
+                        channels: value("channels"),
+                    });
+                }
+                _ => {}
+            }
+            stream.clear();
+        } else if line == "[/FORMAT]" {
+            if let Some(Ok(duration)) = stream.get("duration").map(|d| d.parse::<f64>()) {
+                format.duration_ms = (duration * 1000.0).round() as i64;
+            }
+            stream.clear();
+        } else if let Some((key, value)) = line.split_once('=') {
+            stream.insert(key, value);
+        }
+    }
+    format
+}
+
+/// Parses a frame rate like `30000/1001` or `25`
+fn parse_frame_rate(rate: &str) -> Option<f32> {
+    let fps = match rate.split_once('/') {
+        Some((num, den)) => num.parse::<f32>().ok()? / den.parse::<f32>().ok()?,
+        None => rate.parse().ok()?,
+    };
+    if fps.is_finite() && fps > 0.0 {
+        Some(fps)
+    } else {
+        None
+    }
+}
+
+/// Time in ms of the first keyframe at or after `start_ms` and before `end_ms`
+fn first_keyframe(
+    ffprobe_path: &Path,
+    input_path: &Path,
+    start_ms: i64,
+    end_ms: i64,
+) -> Result<Option<Keyframe>, String> {
+    let interval = format!("{}%{}", seconds(start_ms), seconds(end_ms));
+    let output = ffprobe(
+        ffprobe_path,
+        input_path,
+        &[
+            "-select_streams",
+            "v:0",
+            "-read_intervals",
+            &interval,
+            "-show_entries",
+            "packet=pts_time,flags",
+            "-of",
+            "csv=print_section=0",
+        ],
+    )?;
+    Ok(parse_keyframes(&output).into_iter().find(|keyframe| {
+        keyframe.seconds * 1000.0 >= start_ms as f64 && keyframe.time_ms() < end_ms
+    }))
+}
+
+/// A keyframe of a video as listed by ffprobe
+#[derive(Debug, PartialEq)]
+struct Keyframe {
+    /// The time exactly as printed by ffprobe, e.g. `12.345600`
+    pts_time: String,
+    seconds: f64,
+}
+
+impl Keyframe {
+    /// The time in ms, rounded up so that it is never before the keyframe
+    fn time_ms(&self) -> i64 {
+        (self.seconds * 1000.0).ceil() as i64
+    }
+}
+
+/// Parses the `pts_time,flags` lines of ffprobe into the keyframes, ordered by time
+fn parse_keyframes(output: &str) -> Vec<Keyframe> {
+    let mut keyframes: Vec<Keyframe> = output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.trim().split(',');
+            let pts_time = fields.next()?;
+            let seconds = pts_time.parse::<f64>().ok()?;
+            let flags = fields.next()?;
+            if flags.contains('K') && seconds.is_finite() {
+                Some(Keyframe {
+                    pts_time: pts_time.to_owned(),
+                    seconds,
+                })
+            } else {
+                None
+            }
+        })
+        .collect();
+    keyframes.sort_by(|a, b| a.seconds.partial_cmp(&b.seconds).unwrap());
+    keyframes
+}
+
+/// File name without extension of the condensed video of all clips with the given tag,
+/// e.g. `condensed_red_zone`
+fn condensed_file_stem(tag: &ClipTag) -> String {
+    format!("condensed_{}", file_name_safe(&tag.name))
+}
+
+/// How the clips of a condensed video are concatenated
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ConcatStrategy {
+    /// All clips have the same format, so their streams are copied into the given container
+    StreamCopy { extension: String },
+    /// The clips differ in format and are re-encoded to the format of the first clip
+    ReEncode {
+        width: u32,
+        height: u32,
+        frame_rate: String,
+        /// e.g. "clips differ in resolution"
+        reason: String,
+        with_audio: bool,
+    },
+}
+
+impl std::fmt::Display for ConcatStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConcatStrategy::StreamCopy { extension } => {
+                write!(f, "stream copy into .{}", extension)
+            }
+            ConcatStrategy::ReEncode {
+                width,
+                height,
+                frame_rate,
+                reason,
+                with_audio,
+            } => {
+                write!(
+                    f,
+                    "re-encoded to {}x{} at {} fps because {}",
+                    width, height, frame_rate, reason
+                )?;
+                if !with_audio {
+                    f.write_str(", without audio")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Container of files that mix different containers with the same codecs
+const MIXED_CONTAINER_EXTENSION: &str = "mkv";
+/// Container of re-encoded condensed videos
+const RE_ENCODED_EXTENSION: &str = "mp4";
+
+/// Decides how to concatenate clips of the given formats. `clips` must not be empty.
+fn choose_strategy(clips: &[(PathBuf, MediaFormat)]) -> ConcatStrategy {
+    let (_, first) = &clips[0];
+    let differs = |property: fn(&MediaFormat) -> String| {
+        clips
+            .iter()
+            .any(|(_, format)| property(format) != property(first))
+    };
+    let reason = if differs(|f| f.video_codec.clone()) {
+        Some("clips differ in video codec")
+    } else if differs(|f| format!("{}x{}", f.width, f.height)) {
+        Some("clips differ in resolution")
+    } else if differs(|f| f.frame_rate.clone()) {
+        Some("clips differ in frame rate")
+    } else if differs(|f| f.pix_fmt.clone()) {
+        Some("clips differ in pixel format")
+    } else if differs(|f| format!("{:?}", f.audio)) {
+        Some("clips differ in audio format")
+    } else {
+        None
+    };
+
+    match reason {
+        None => {
+            let extension = |path: &PathBuf| {
+                path.extension()
+                    .and_then(|extension| extension.to_str())
+                    .map(|extension| extension.to_lowercase())
+            };
+            let first_extension = extension(&clips[0].0);
+            let extension = match first_extension {
+                Some(first_extension)
+                    if clips
+                        .iter()
+                        .all(|(path, _)| extension(path).as_ref() == Some(&first_extension)) =>
+                {
+                    first_extension
+                }
+                _ => MIXED_CONTAINER_EXTENSION.to_owned(),
+            };
+            ConcatStrategy::StreamCopy { extension }
+        }
+        Some(reason) => ConcatStrategy::ReEncode {
+            width: first.width,
+            height: first.height,
+            frame_rate: first.frame_rate.clone(),
+            reason: reason.to_owned(),
+            with_audio: clips.iter().all(|(_, format)| format.audio.is_some()),
+        },
+    }
+}
+
+/// Format that clips are re-encoded to, see `ConcatStrategy::ReEncode`
+struct TargetFormat<'a> {
+    width: u32,
+    height: u32,
+    frame_rate: &'a str,
+    with_audio: bool,
+}
+
+impl TargetFormat<'_> {
+    /// Codec arguments shared by all re-encoded parts, so that they can be concatenated
+    fn codec_args(&self, encoder: &EncoderPreset) -> Vec<OsString> {
+        let mut codec_args = encoder.codec_args(&encoder.video_codec);
+        if self.with_audio {
+            // a common sample layout, otherwise the audio of the re-encoded clips cannot be copied
+            codec_args.extend(vec!["-ar".into(), "48000".into(), "-ac".into(), "2".into()]);
+        } else {
+            if let Some(i) = codec_args.iter().position(|arg| arg == "-c:a") {
+                codec_args.truncate(i);
+            }
+            codec_args.push("-an".into());
+        }
+        codec_args.extend(vec!["-video_track_timescale".into(), "90000".into()]);
+        codec_args
+    }
+}
+
+/// Arguments for ffmpeg to re-encode a clip to the target format, with additional video
+/// filters applied. The output file has to be appended.
+fn normalize_args(
+    input_path: &Path,
+    target: &TargetFormat,
+    filters: &[String],
+    encoder: &EncoderPreset,
+) -> Vec<OsString> {
+    let mut filter = format!(
+        "scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2,setsar=1,fps={fps}",
+        w = target.width,
+        h = target.height,
+        fps = target.frame_rate
+    );
+    for extra_filter in filters {
+        filter += ",";
+        filter += extra_filter;
+    }
+    filter += ",format=yuv420p";
+    let mut args: Vec<OsString> = vec!["-i".into(), input_path.into(), "-vf".into(), filter.into()];
+    args.extend(target.codec_args(encoder));
+    args
+}
+
+/// Arguments for ffmpeg to create a title card in the target format which shows `lines` on a
+/// black background. The output file has to be appended.
+fn title_card_args(
+    lines: &[String],
+    options: &ReelOptions,
+    target: &TargetFormat,
+    encoder: &EncoderPreset,
+) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec![
+        "-f".into(),
+        "lavfi".into(),
+        "-i".into(),
+        format!(
+            "color=c=black:s={}x{}:r={}",
+            target.width, target.height, target.frame_rate
+        )
+        .into(),
+    ];
+    if target.with_audio {
+        args.extend(vec![
+            "-f".into(),
+            "lavfi".into(),
+            "-i".into(),
+            "anullsrc=r=48000:cl=stereo".into(),
+            "-map".into(),
+            "0:v".into(),
+            "-map".into(),
+            "1:a".into(),
+        ]);
+    }
+
+    // the lines are centered, the first one is the clip number
+    let line_count = lines.len();
+    let mut filters: Vec<String> = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            drawtext(
+                line,
+                options.font_file.as_deref(),
+                if i == 0 { "h/10" } else { "h/16" },
+                &format!("h/2-{}*h/18+{}*h/9", line_count, i),
+                false,
+            )
+        })
+        .collect();
+    filters.push("format=yuv420p".to_owned());
+    args.extend(vec![
+        "-t".into(),
+        format!("{}", options.title_card_seconds).into(),
+        "-vf".into(),
+        filters.join(",").into(),
+    ]);
+    args.extend(target.codec_args(encoder));
+    args
+}
+
+/// A horizontally centered `drawtext` filter. `y` is an expression of the height `h`.
+fn drawtext(text: &str, font_file: Option<&str>, font_size: &str, y: &str, boxed: bool) -> String {
+    let mut filter = format!(
+        "drawtext=expansion=none:text={}:fontcolor=white:fontsize={}:x=(w-text_w)/2:y={}",
+        escape_filter_value(text),
+        font_size,
+        y
+    );
+    if let Some(font_file) = font_file {
+        filter += ":fontfile=";
+        filter += &escape_filter_value(font_file);
+    }
+    if boxed {
+        filter += ":box=1:boxcolor=black@0.6:boxborderw=10";
+    }
+    filter
+}
+
+/// Escapes the value of a filter option within a filter graph given with `-vf`.
+/// There are two levels of escaping: for the option value and for the filter graph.
+fn escape_filter_value(value: &str) -> String {
+    let escape = |text: &str, special: &[char]| {
+        let mut escaped = String::new();
+        for c in text.chars() {
+            if special.contains(&c) {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
+    };
+    let option_value = escape(value, &['\\', '\'', ':']);
+    escape(&option_value, &['\\', '\'', '[', ']', ',', ';'])
+}
+
+/// Title cards and captions drawn onto the clips of a reel
+pub(crate) struct Decorations<'a> {
+    pub(crate) options: &'a ReelOptions,
+    /// Texts of every clip
+    pub(crate) texts: &'a [ReelClipTexts],
+}
+
+/// A video created by concatenating clips
+#[derive(Debug)]
+pub(crate) struct CondensedVideo {
+    pub(crate) path: PathBuf,
+    pub(crate) clip_count: usize,
+    pub(crate) strategy: ConcatStrategy,
+}
+
+/// Probes `clip_paths` and creates the job which concatenates them in the given order into
+/// `<output_dir_path>/<output_file_stem>.<extension>`
+pub(crate) fn concat_job(
+    processor: &dyn MediaProcessor,
+    description: String,
+    clip_paths: &[PathBuf],
+    output_dir_path: &Path,
+    output_file_stem: &str,
+    encoder: &EncoderPreset,
+    decorations: Option<Decorations>,
+) -> Result<(Job, CondensedVideo), String> {
+    let clips = clip_paths
+        .iter()
+        .map(|path| Ok((path.clone(), processor.probe(path)?)))
+        .collect::<Result<Vec<_>, String>>()?;
+    processor.concat(
+        description,
+        &clips,
+        output_dir_path,
+        output_file_stem,
+        encoder,
+        decorations,
+    )
+}
+
+/// Clips of the same format are copied into their own container, clips of different formats are
+/// re-encoded with `encoder` to the format of the first clip.
+fn concat_probed_job(
+    description: String,
+    clips: &[(PathBuf, MediaFormat)],
+    output_dir_path: &Path,
+    output_file_stem: &str,
+    encoder: &EncoderPreset,
+    decorations: Option<Decorations>,
+) -> Result<(Job, CondensedVideo), String> {
+    if clips.is_empty() {
+        return Err("there are no clips to concatenate".to_owned());
+    }
+    let decorations = decorations
+        .filter(|decorations| decorations.options.title_cards || decorations.options.captions);
+
+    let mut strategy = choose_strategy(clips);
+    if let (Some(_), ConcatStrategy::StreamCopy { .. }) = (&decorations, &strategy) {
+        let (_, first) = &clips[0];
+        strategy = ConcatStrategy::ReEncode {
+            width: first.width,
+            height: first.height,
+            frame_rate: first.frame_rate.clone(),
+            reason: "title cards or captions are drawn".to_owned(),
+            with_audio: clips.iter().all(|(_, format)| format.audio.is_some()),
+        };
+    }
+    let extension = match &strategy {
+        ConcatStrategy::StreamCopy { extension } => extension.as_str(),
+        ConcatStrategy::ReEncode { .. } => RE_ENCODED_EXTENSION,
+    };
+    let output_file_path = output_dir_path.join(format!("{}.{}", output_file_stem, extension));
+
+    let mut steps = Vec::new();
+    let files: Vec<PathBuf> = match &strategy {
+        ConcatStrategy::StreamCopy { .. } => clips.iter().map(|(path, _)| path.clone()).collect(),
+        ConcatStrategy::ReEncode {
+            width,
+            height,
+            frame_rate,
+            with_audio,
+            ..
+        } => {
+            let target = TargetFormat {
+                width: *width,
+                height: *height,
+                frame_rate,
+                with_audio: *with_audio,
+            };
+            let part_path = |i: usize, part: &str| {
+                output_dir_path.join(format!(
+                    "{}.{}{:05}.{}",
+                    output_file_stem, part, i, RE_ENCODED_EXTENSION
+                ))
+            };
+            // the re-encoded clips are intermediate files of the job, so they are removed again
+            for (i, (clip_path, format)) in clips.iter().enumerate() {
+                let mut filters = Vec::new();
+                if let Some(decorations) = &decorations {
+                    let options = decorations.options;
+                    let texts = &decorations.texts[i];
+                    if options.title_cards {
+                        steps.push(JobStep {
+                            args: title_card_args(&texts.title_lines, options, &target, encoder),
+                            output: part_path(i, "card"),
+                            duration_ms: (options.title_card_seconds * 1000.0) as i64,
+                        });
+                    }
+                    if options.captions {
+                        filters.push(drawtext(
+                            &texts.caption,
+                            options.font_file.as_deref(),
+                            "h/28",
+                            "h-text_h-h/20",
+                            true,
+                        ));
+                    }
+                }
+                steps.push(JobStep {
+                    args: normalize_args(clip_path, &target, &filters, encoder),
+                    output: part_path(i, "part"),
+                    duration_ms: format.duration_ms,
+                });
+            }
+            steps.iter().map(|step| step.output.clone()).collect()
+        }
+    };
+
+    let files: Vec<&Path> = files.iter().map(|path| path.as_path()).collect();
+    let index_file = index_file(
+        output_dir_path.join(format!("{}.index.txt", output_file_stem)),
+        &files,
+    );
+    // the output is as long as all re-encoded parts or all copied clips together
+    let output_duration_ms = if steps.is_empty() {
+        clips.iter().map(|(_, format)| format.duration_ms).sum()
+    } else {
+        steps.iter().map(|step| step.duration_ms).sum()
+    };
+    steps.push(JobStep {
+        args: vec![
+            "-f".into(),
+            "concat".into(),
+            "-safe".into(),
+            "0".into(),
+            "-i".into(),
+            index_file.path.as_os_str().to_owned(),
+            "-c".into(),
+            "copy".into(),
+        ],
+        output: output_file_path.clone(),
+        duration_ms: output_duration_ms,
+    });
+
+    let job = Job {
+        description,
+        steps,
+        temp_files: vec![index_file],
+        overwrite: true,
+        prepare: None,
+    };
+    let condensed_video = CondensedVideo {
+        path: output_file_path,
+        clip_count: clips.len(),
+        strategy,
+    };
+    Ok((job, condensed_video))
+}
+
+/// Creates the jobs which concatenate the clips in `input_dir_path` into one video per tag and
+/// one video of all clips. A clip with several tags is part of the video of each of its tags.
+pub(crate) fn concat_jobs(
+    processor: &dyn MediaProcessor,
+    input_dir_path: &Path,
+    output_dir_path: &Path,
+    tags: &[ClipTag],
+    encoder: &EncoderPreset,
+) -> Result<Vec<(Job, CondensedVideo)>, String> {
+    let mut sorted_by_tag = vec![BTreeSet::new(); tags.len()];
     let mut sorted_all = BTreeSet::new();
-    for entry_result in std::fs::read_dir(input_dir_path).unwrap() {
-        let entry = entry_result.unwrap();
-        let file_path = entry.path();
+    let clip_paths = crate::list_media(input_dir_path)
+        .map_err(|e| format!("could not read {:?}: {}", input_dir_path, e))?;
+    let mut formats = HashMap::new();
+    for file_path in clip_paths {
         let stem = file_path.file_stem().unwrap().to_str().unwrap();
-        if stem.ends_with(crate::CLIP_SUFFIX_OFFENSE) {
-            sorted_offense.insert(file_path.clone());
-        } else if stem.ends_with(crate::CLIP_SUFFIX_DEFENSE) {
-            sorted_defense.insert(file_path.clone());
+        let suffixes = clip_tag_suffixes(stem);
+        for (tag, sorted) in tags.iter().zip(sorted_by_tag.iter_mut()) {
+            if suffixes.contains(&tag.suffix.as_str()) {
+                sorted.insert(file_path.clone());
+            }
         }
-        sorted_all.insert(file_path.clone());
+        formats.insert(file_path.clone(), processor.probe(&file_path)?);
+        sorted_all.insert(file_path);
     }
 
-    let mut index_file = std::fs::File::create(index_file_path).unwrap();
+    let mut reels: Vec<(BTreeSet<PathBuf>, String)> = tags
+        .iter()
+        .zip(sorted_by_tag)
+        .map(|(tag, sorted)| (sorted, condensed_file_stem(tag)))
+        .collect();
+    reels.push((sorted_all, "condensed_all".to_owned()));
+
+    reels
+        .iter()
+        .filter(|(sorted, _stem)| !sorted.is_empty())
+        .map(|(sorted, output_file_stem)| {
+            let clips: Vec<(PathBuf, MediaFormat)> = sorted
+                .iter()
+                .map(|path| (path.clone(), formats[path].clone()))
+                .collect();
+            processor.concat(
+                format!("concatenate {}", output_file_stem),
+                &clips,
+                output_dir_path,
+                output_file_stem,
+                encoder,
+                None,
+            )
+        })
+        .collect()
+}
 
-    let mut result = Ok(());
-    let off_out = output_dir_path.join("condensed_offense.mp4");
-    let def_out = output_dir_path.join("condensed_defense.mp4");
-    let all_out = output_dir_path.join("condensed_all.mp4");
-    for (sorted, output_file_pathbuf) in [
-        (sorted_offense, off_out),
-        (sorted_defense, def_out),
-        (sorted_all, all_out),
+/// Creates the jobs which write the animation `<output_file_stem>.<gif|webp>` and the contact
+/// sheet `<output_file_stem>_sheet.jpg` of `start_ms` to `end_ms` of `input_path` into
+/// `output_dir_path`. Existing previews are replaced.
+pub(crate) fn preview_jobs(
+    processor: &dyn MediaProcessor,
+    input_path: &Path,
+    start_ms: i64,
+    end_ms: i64,
+    settings: &PreviewSettings,
+    output_dir_path: &Path,
+    output_file_stem: &str,
+) -> Vec<Job> {
+    let animation_path = output_dir_path.join(format!(
+        "{}.{}",
+        output_file_stem,
+        settings.format.extension()
+    ));
+    let sheet_path = output_dir_path.join(format!("{}_sheet.jpg", output_file_stem));
+    vec![
+        processor.animation(
+            format!("animate {}", output_file_stem),
+            input_path,
+            start_ms,
+            end_ms,
+            settings,
+            &animation_path,
+        ),
+        processor.contact_sheet(
+            format!("contact sheet of {}", output_file_stem),
+            input_path,
+            start_ms,
+            end_ms,
+            settings,
+            &sheet_path,
+        ),
     ]
-    .iter()
-    .filter(|(sorted, _path)| !sorted.is_empty())
-    {
-        let output_file_path = output_file_pathbuf.as_path();
-        for file_path in sorted.iter() {
-            let index_entry = format!("file '{}'\n", file_path.to_str().unwrap());
-            index_file.write_all(index_entry.as_bytes()).unwrap();
-        }
-
-        index_file.flush().unwrap();
-
-        if let Ok(output) = Command::new("ffmpeg")
-            .arg("-f")
-            .arg("concat")
-            .arg("-safe")
-            .arg("0")
-            .arg("-i")
-            .arg(index_file_path.to_str().unwrap())
-            .arg("-c")
-            .arg("copy")
-            .arg("-y")
-            .arg(output_file_path.to_str().unwrap())
-            .output()
-        {
-            if !output.status.success() {
-                let code = match output.status.code() {
-                    Some(c) => format!("{}", c),
-                    None => "?".to_owned(),
-                };
-                result = Err(format!(
-                    "error from ffmpeg[code:{}]: {}",
-                    code,
-                    String::from_utf8(output.stderr).unwrap()
-                ));
-            }
+}
+
+/// Like `concat_jobs`, but runs the jobs and waits for them
+pub(crate) fn concat(
+    processor: Arc<dyn MediaProcessor>,
+    input_dir_path: &Path,
+    output_dir_path: &Path,
+    tags: &[ClipTag],
+    encoder: &EncoderPreset,
+) -> Result<Vec<CondensedVideo>, String> {
+    let jobs_of_tags = concat_jobs(
+        processor.as_ref(),
+        input_dir_path,
+        output_dir_path,
+        tags,
+        encoder,
+    )?;
+    let mut jobs = JobQueue::new(processor);
+    let mut condensed_videos = HashMap::new();
+    for (job, condensed_video) in jobs_of_tags {
+        condensed_videos.insert(jobs.submit(job), condensed_video);
+    }
+
+    let mut created = Vec::new();
+    let mut errors = Vec::new();
+    for update in jobs.wait_all() {
+        match update.status {
+            JobStatus::Succeeded => created.extend(condensed_videos.remove(&update.id)),
+            JobStatus::Failed(e) => errors.push(format!("{}: {}", update.description, e)),
+            _ => {}
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(created)
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+/// Oldest release of ffmpeg and ffprobe that VAC works with
+const MIN_TOOL_VERSION: (u32, u32) = (4, 0);
+
+/// Result of looking for ffmpeg or ffprobe
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ToolCheck {
+    pub(crate) name: &'static str,
+    /// Where the tool was found. If it was not found, just its name.
+    pub(crate) path: PathBuf,
+    /// e.g. "4.1.3", `None` if the tool could not be run
+    pub(crate) version: Option<String>,
+    /// Why the tool cannot be used
+    pub(crate) problem: Option<String>,
+}
+
+impl std::fmt::Display for ToolCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.problem, &self.version) {
+            (Some(problem), _) => write!(f, "{}: {}", self.name, problem),
+            (None, Some(version)) => write!(f, "{} {}", self.name, version),
+            (None, None) => f.write_str(self.name),
+        }
+    }
+}
+
+/// Directories next to the executable and the working directory, where ffmpeg may be bundled
+fn bundled_tool_dirs() -> Vec<PathBuf> {
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.to_path_buf()));
+    let mut dirs = Vec::new();
+    for dir in exe_dir.into_iter().chain(std::env::current_dir().ok()) {
+        dirs.push(dir.join("ffmpeg").join("bin"));
+        dirs.push(dir);
+    }
+    dirs
+}
+
+/// Path of the tool `name`: the configured path, or else the first match in `search_dirs`
+fn locate_tool(
+    name: &str,
+    configured: Option<&Path>,
+    search_dirs: &[PathBuf],
+) -> Result<PathBuf, String> {
+    if let Some(path) = configured {
+        return if path.is_file() {
+            Ok(path.to_path_buf())
         } else {
-            result = Err("could not execute ffmpeg".to_owned());
+            Err(format!("{:?} from settings.toml does not exist", path))
+        };
+    }
+    let file_name = format!("{}{}", name, std::env::consts::EXE_SUFFIX);
+    search_dirs
+        .iter()
+        .map(|dir| dir.join(&file_name))
+        .find(|path| path.is_file())
+        .ok_or_else(|| "not found next to VAC or in the PATH".to_owned())
+}
+
+/// Parses the version out of the first line of `ffmpeg -version`,
+/// e.g. `ffmpeg version 4.1.3 Copyright (c) 2000-2019 the FFmpeg developers`
+fn parse_version_line(line: &str) -> Option<&str> {
+    let mut words = line.split_whitespace();
+    words.find(|word| *word == "version")?;
+    words.next()
+}
+
+/// Major and minor number of a release version like `4.1.3`, `n6.0` or `5.1.2-0ubuntu1`.
+/// `None` for versions without numbers, e.g. of builds from git like `N-109468-gd39b34128d`.
+fn version_number(version: &str) -> Option<(u32, u32)> {
+    let version = version.strip_prefix('n').unwrap_or(version);
+    let mut numbers = version
+        .split(|c: char| !c.is_ascii_digit())
+        .map(|number| number.parse::<u32>());
+    let major = numbers.next()?.ok()?;
+    let minor = numbers.next().and_then(Result::ok).unwrap_or(0);
+    Some((major, minor))
+}
+
+/// Finds the tool `name` and checks that it runs and is new enough
+fn check_tool(name: &'static str, configured: Option<&Path>) -> ToolCheck {
+    let mut search_dirs = bundled_tool_dirs();
+    if let Some(paths) = std::env::var_os("PATH") {
+        search_dirs.extend(std::env::split_paths(&paths));
+    }
+    let mut check = ToolCheck {
+        name,
+        path: configured.map_or_else(|| PathBuf::from(name), Path::to_path_buf),
+        version: None,
+        problem: None,
+    };
+    check.path = match locate_tool(name, configured, &search_dirs) {
+        Ok(path) => path,
+        Err(problem) => {
+            check.problem = Some(format!(
+                "{}. Install it or set its path in settings.toml",
+                problem
+            ));
+            return check;
+        }
+    };
+
+    let output = match Command::new(&check.path).arg("-version").output() {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => {
+            check.problem = Some(format!(
+                "{:?} -version failed with {}",
+                check.path, output.status
+            ));
+            return check;
         }
+        Err(e) => {
+            check.problem = Some(format!("could not execute {:?}: {}", check.path, e));
+            return check;
+        }
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = stdout.lines().next().and_then(parse_version_line);
+    check.version = version.map(str::to_owned);
+    match version.map(version_number) {
+        Some(Some(number)) if number < MIN_TOOL_VERSION => {
+            check.problem = Some(format!(
+                "version {} is too old, {}.{} or newer is needed",
+                version.unwrap_or_default(),
+                MIN_TOOL_VERSION.0,
+                MIN_TOOL_VERSION.1
+            ));
+        }
+        Some(_) => {}
+        None => check.problem = Some(format!("{:?} does not look like {}", check.path, name)),
+    }
+    check
+}
+
+/// Runs the ffmpeg and ffprobe executables
+pub(crate) struct FfmpegProcessor {
+    ffmpeg_path: PathBuf,
+    ffprobe_path: PathBuf,
+}
+
+impl FfmpegProcessor {
+    /// Looks for ffmpeg and ffprobe at the paths in the settings, next to the executable, in the
+    /// working directory and in the `PATH`, and checks their versions
+    pub(crate) fn discover(settings: &ToolSettings) -> (FfmpegProcessor, Vec<ToolCheck>) {
+        let ffmpeg = check_tool("ffmpeg", settings.ffmpeg.as_deref());
+        let ffprobe = check_tool("ffprobe", settings.ffprobe.as_deref());
+        let processor = FfmpegProcessor {
+            ffmpeg_path: ffmpeg.path.clone(),
+            ffprobe_path: ffprobe.path.clone(),
+        };
+        (processor, vec![ffmpeg, ffprobe])
+    }
+}
+
+impl MediaProcessor for FfmpegProcessor {
+    fn probe(&self, input_path: &Path) -> Result<MediaFormat, String> {
+        probe_media_format(&self.ffprobe_path, input_path)
+    }
+
+    fn cut(
+        &self,
+        description: String,
+        input_path: &Path,
+        start_ms: i64,
+        end_ms: i64,
+        output_path: &Path,
+        settings: &CutSettings,
+    ) -> Job {
+        cut_job(
+            &self.ffprobe_path,
+            description,
+            input_path,
+            start_ms,
+            end_ms,
+            output_path,
+            settings,
+        )
+    }
+
+    fn concat(
+        &self,
+        description: String,
+        clips: &[(PathBuf, MediaFormat)],
+        output_dir_path: &Path,
+        output_file_stem: &str,
+        encoder: &EncoderPreset,
+        decorations: Option<Decorations>,
+    ) -> Result<(Job, CondensedVideo), String> {
+        concat_probed_job(
+            description,
+            clips,
+            output_dir_path,
+            output_file_stem,
+            encoder,
+            decorations,
+        )
+    }
+
+    fn thumbnail(
+        &self,
+        description: String,
+        input_path: &Path,
+        time_ms: i64,
+        output_path: &Path,
+    ) -> Job {
+        thumbnail_job(description, input_path, time_ms, output_path)
+    }
+
+    fn animation(
+        &self,
+        description: String,
+        input_path: &Path,
+        start_ms: i64,
+        end_ms: i64,
+        settings: &PreviewSettings,
+        output_path: &Path,
+    ) -> Job {
+        animation_job(
+            description,
+            input_path,
+            start_ms,
+            end_ms,
+            settings,
+            output_path,
+        )
     }
 
-    std::fs::remove_file(index_file_path).unwrap();
+    fn contact_sheet(
+        &self,
+        description: String,
+        input_path: &Path,
+        start_ms: i64,
+        end_ms: i64,
+        settings: &PreviewSettings,
+        output_path: &Path,
+    ) -> Job {
+        contact_sheet_job(
+            description,
+            input_path,
+            start_ms,
+            end_ms,
+            settings,
+            output_path,
+        )
+    }
+
+    fn extract_audio(
+        &self,
+        description: String,
+        input_path: &Path,
+        duration_ms: i64,
+        sample_rate: u32,
+        output_path: &Path,
+    ) -> Job {
+        extract_audio_job(
+            description,
+            input_path,
+            duration_ms,
+            sample_rate,
+            output_path,
+        )
+    }
+
+    fn run_step(
+        &self,
+        step: &JobStep,
+        overwrite: bool,
+        send_progress: &dyn Fn(i64),
+        is_cancelled: &dyn Fn() -> bool,
+    ) -> JobStatus {
+        run_step(
+            &self.ffmpeg_path,
+            step,
+            overwrite,
+            send_progress,
+            is_cancelled,
+        )
+    }
+}
 
-    result
+/// Parses a line of ffmpeg's `-progress` output and returns the time written so far in ms.
+/// Note that ffmpeg reports `out_time_ms` in microseconds, just like `out_time_us`.
+fn parse_progress_line(line: &str) -> Option<i64> {
+    let mut parts = line.trim().splitn(2, '=');
+    match (parts.next()?, parts.next()?) {
+        ("out_time_us", value) | ("out_time_ms", value) => Some(value.parse::<i64>().ok()? / 1000),
+        _ => None,
+    }
+}
+
+fn run_step(
+    ffmpeg_path: &Path,
+    step: &JobStep,
+    overwrite: bool,
+    send_progress: &dyn Fn(i64),
+    is_cancelled: &dyn Fn() -> bool,
+) -> JobStatus {
+    let mut child: Child = match Command::new(ffmpeg_path)
+        .arg("-nostdin")
+        .arg("-nostats")
+        .arg("-progress")
+        .arg("pipe:1")
+        .args(&step.args)
+        .arg(if overwrite { "-y" } else { "-n" })
+        .arg(&step.output)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            return JobStatus::Failed(format!("could not execute ffmpeg {:?}: {}", ffmpeg_path, e))
+        }
+    };
+
+    let (progress_tx, progress_rx) = channel();
+    let stdout = child.stdout.take().unwrap();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            if let Some(time_ms) = parse_progress_line(&line) {
+                if progress_tx.send(time_ms).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    let mut stderr = child.stderr.take().unwrap();
+    let stderr_reader = std::thread::spawn(move || {
+        let mut output = String::new();
+        let _ = stderr.read_to_string(&mut output);
+        output
+    });
+
+    let status = loop {
+        if is_cancelled() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return JobStatus::Cancelled;
+        }
+
+        if let Some(time_ms) = progress_rx.try_iter().last() {
+            send_progress(time_ms);
+        }
+
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) => std::thread::sleep(Duration::from_millis(50)),
+            Err(e) => return JobStatus::Failed(format!("error waiting for ffmpeg: {}", e)),
+        }
+    };
+
+    let stderr_output = stderr_reader.join().unwrap_or_default();
+    if status.success() {
+        JobStatus::Succeeded
+    } else {
+        let code = match status.code() {
+            Some(c) => format!("{}", c),
+            None => "?".to_owned(),
+        };
+        JobStatus::Failed(format!(
+            "error from ffmpeg[code:{}]: {}",
+            code,
+            stderr_output.trim()
+        ))
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::media::{MediaCall, RecordingProcessor};
     use std::path::Path;
     #[test]
     fn test_ffmpeg_concat() {
@@ -92,7 +1660,13 @@ mod test {
             .join("ressources")
             .join("testvideo.mp4_clips");
 
-        let result = concat(&input_dir_path, &output_dir_path);
+        let result = concat(
+            Arc::new(FfmpegProcessor::discover(&ToolSettings::default()).0),
+            &input_dir_path,
+            &output_dir_path,
+            &ClipTag::defaults(),
+            &EncoderPreset::default(),
+        );
         if let Err(e) = result {
             println!("{}", e);
             assert!(false);
@@ -107,4 +1681,374 @@ mod test {
         assert!(entries.iter().any(|e| e == "condensed_defense.mp4"));
         assert!(entries.iter().any(|e| e == "condensed_offense.mp4"));
     }
+
+    #[test]
+    fn test_concat_with_recording_processor() {
+        let project_dir = Path::new("tests").join("output").join("concat_recorded");
+        let _ = std::fs::remove_dir_all(&project_dir);
+        let input_dir_path = project_dir.join("_clips");
+        let output_dir_path = project_dir.join("_condensed");
+        std::fs::create_dir_all(&input_dir_path).unwrap();
+        let mut processor = RecordingProcessor::default();
+        let clip_paths: Vec<PathBuf> = [
+            "game.mp4_00001000Off.mp4",
+            "game.mp4_00002000Def.mp4",
+            "game.mp4_00003000Off-Def.mp4",
+        ]
+        .iter()
+        .map(|file_name| input_dir_path.join(file_name))
+        .collect();
+        for clip_path in clip_paths.iter() {
+            std::fs::write(clip_path, "").unwrap();
+            processor
+                .formats
+                .insert(clip_path.clone(), MediaFormat::default());
+        }
+        processor
+            .failing_outputs
+            .insert(output_dir_path.join("condensed_defense.mp4"));
+        let processor = Arc::new(processor);
+
+        let result = concat(
+            processor.clone(),
+            &input_dir_path,
+            &output_dir_path,
+            &ClipTag::defaults(),
+            &EncoderPreset::default(),
+        );
+        assert!(result
+            .unwrap_err()
+            .contains("concatenate condensed_defense"));
+
+        let concatenated: Vec<(Vec<PathBuf>, PathBuf)> = processor
+            .calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                MediaCall::Concat { clips, output } => Some((clips, output)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            concatenated,
+            vec![
+                (
+                    vec![clip_paths[0].clone(), clip_paths[2].clone()],
+                    output_dir_path.join("condensed_offense.mp4")
+                ),
+                (
+                    vec![clip_paths[1].clone(), clip_paths[2].clone()],
+                    output_dir_path.join("condensed_defense.mp4")
+                ),
+                (
+                    clip_paths.clone(),
+                    output_dir_path.join("condensed_all.mp4")
+                ),
+            ]
+        );
+        assert!(!output_dir_path.exists());
+
+        std::fs::remove_dir_all(&project_dir).unwrap();
+    }
+
+    #[test]
+    fn test_choose_concat_strategy() {
+        let hd = MediaFormat {
+            video_codec: "h264".to_owned(),
+            pix_fmt: "yuv420p".to_owned(),
+            width: 1920,
+            height: 1080,
+            frame_rate: "30/1".to_owned(),
+            audio: Some(AudioFormat {
+                codec_name: "aac".to_owned(),
+                sample_rate: "48000".to_owned(),
+                channels: "2".to_owned(),
+            }),
+            duration_ms: 4000,
+        };
+        let clip = |name: &str, format: &MediaFormat| (PathBuf::from(name), format.clone());
+
+        assert_eq!(
+            choose_strategy(&[clip("a.MOV", &hd), clip("b.mov", &hd)]),
+            ConcatStrategy::StreamCopy {
+                extension: "mov".to_owned()
+            }
+        );
+        assert_eq!(
+            choose_strategy(&[clip("a.mov", &hd), clip("b.mp4", &hd)]),
+            ConcatStrategy::StreamCopy {
+                extension: "mkv".to_owned()
+            }
+        );
+
+        let sd = MediaFormat {
+            width: 1280,
+            height: 720,
+            audio: None,
+            ..hd.clone()
+        };
+        let strategy = choose_strategy(&[clip("a.mp4", &hd), clip("b.mp4", &sd)]);
+        assert_eq!(
+            strategy,
+            ConcatStrategy::ReEncode {
+                width: 1920,
+                height: 1080,
+                frame_rate: "30/1".to_owned(),
+                reason: "clips differ in resolution".to_owned(),
+                with_audio: false,
+            }
+        );
+        assert_eq!(
+            strategy.to_string(),
+            "re-encoded to 1920x1080 at 30/1 fps because clips differ in resolution, without audio"
+        );
+    }
+
+    #[test]
+    fn test_escape_filter_value() {
+        // the example of the ffmpeg documentation on filtergraph escaping
+        assert_eq!(
+            escape_filter_value("this is a 'string': may contain one, or more, special characters"),
+            "this is a \\\\\\'string\\\\\\'\\\\: may contain one\\, or more\\, special characters"
+        );
+        assert_eq!(escape_filter_value("3rd & 7"), "3rd & 7");
+    }
+
+    #[test]
+    fn test_index_file() {
+        let index = index_file(
+            PathBuf::from("index.txt"),
+            &[Path::new("clips/a.mp4"), Path::new("clips/Tom's play.mp4")],
+        );
+        assert_eq!(
+            index.content,
+            "file 'clips/a.mp4'\nfile 'clips/Tom'\\''s play.mp4'\n"
+        );
+    }
+
+    #[test]
+    fn test_reel_job_with_title_cards() {
+        let output_dir_path = Path::new("tests").join("output").join("reel_job");
+        let _ = std::fs::remove_dir_all(&output_dir_path);
+        std::fs::create_dir_all(&output_dir_path).unwrap();
+        let format = MediaFormat {
+            video_codec: "h264".to_owned(),
+            pix_fmt: "yuv420p".to_owned(),
+            width: 1280,
+            height: 720,
+            frame_rate: "25/1".to_owned(),
+            audio: None,
+            duration_ms: 4000,
+        };
+        let clips = vec![
+            (PathBuf::from("a.mp4"), format.clone()),
+            (PathBuf::from("b.mp4"), format),
+        ];
+        let texts: Vec<ReelClipTexts> = (1..=2)
+            .map(|i| ReelClipTexts {
+                title_lines: vec![format!("Clip {} / 2", i)],
+                caption: format!("{}/2", i),
+            })
+            .collect();
+        let options = ReelOptions {
+            title_cards: true,
+            ..Default::default()
+        };
+
+        let (job, video) = concat_probed_job(
+            "reel".to_owned(),
+            &clips,
+            &output_dir_path,
+            "meeting",
+            &EncoderPreset::default(),
+            Some(Decorations {
+                options: &options,
+                texts: &texts,
+            }),
+        )
+        .unwrap();
+        assert!(matches!(video.strategy, ConcatStrategy::ReEncode { .. }));
+        assert_eq!(video.path, output_dir_path.join("meeting.mp4"));
+        // card and clip for both clips, then the concatenation
+        assert_eq!(job.steps.len(), 5);
+        assert!(job.steps[0].output.ends_with("meeting.card00000.mp4"));
+        assert!(job.steps[1].output.ends_with("meeting.part00000.mp4"));
+        assert_eq!(job.steps[4].duration_ms, 2 * 3000 + 2 * 4000);
+        assert!(job.temp_files[0].path.ends_with("meeting.index.txt"));
+        assert_eq!(job.temp_files[0].content.lines().count(), 4);
+        assert!(!job.temp_files[0].path.exists());
+
+        std::fs::remove_dir_all(&output_dir_path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_probe_output() {
+        let packets = "0.000000,K_\n0.033367,__\n2.002000,K_\n2.035367,__\n";
+        let keyframe_times: Vec<i64> = parse_keyframes(packets)
+            .iter()
+            .map(Keyframe::time_ms)
+            .collect();
+        assert_eq!(keyframe_times, vec![0, 2002]);
+        // the exact time is kept for ffmpeg, the time in ms is rounded up
+        assert_eq!(
+            parse_keyframes("12.3456,K_\n12.4,__\n"),
+            vec![Keyframe {
+                pts_time: "12.3456".to_owned(),
+                seconds: 12.3456,
+            }]
+        );
+        assert_eq!(parse_keyframes("12.3456,K_\n")[0].time_ms(), 12346);
+
+        let format = parse_media_format(
+            "[STREAM]\ncodec_name=h264\ncodec_type=video\nwidth=1920\nheight=1080\n\
+             pix_fmt=yuv420p\nr_frame_rate=30000/1001\n[/STREAM]\n\
+             [STREAM]\ncodec_name=aac\ncodec_type=audio\nsample_rate=48000\nchannels=2\n\
+             r_frame_rate=0/0\n[/STREAM]\n[FORMAT]\nduration=12.345000\n[/FORMAT]\n",
+        );
+        assert_eq!(format.video_codec, "h264");
+        assert_eq!(format.pix_fmt, "yuv420p");
+        assert_eq!((format.width, format.height), (1920, 1080));
+        assert_eq!(format.frame_rate, "30000/1001");
+        assert_eq!(format.audio.unwrap().codec_name, "aac");
+        assert_eq!(format.duration_ms, 12345);
+
+        assert_eq!(parse_frame_rate("25/1"), Some(25.0));
+        assert!((parse_frame_rate("30000/1001").unwrap() - 29.97).abs() < 0.01);
+        assert_eq!(parse_frame_rate("0/0"), None);
+    }
+
+    #[test]
+    fn test_parse_progress_line() {
+        assert_eq!(parse_progress_line("out_time_us=2500000"), Some(2500));
+        assert_eq!(parse_progress_line("out_time_ms=2500000\n"), Some(2500));
+        assert_eq!(parse_progress_line("out_time=00:00:02.500000"), None);
+        assert_eq!(parse_progress_line("out_time_us=N/A"), None);
+        assert_eq!(parse_progress_line("progress=end"), None);
+    }
+
+    #[test]
+    fn test_tool_versions() {
+        let line = "ffmpeg version 4.1.3 Copyright (c) 2000-2019 the FFmpeg developers";
+        assert_eq!(parse_version_line(line), Some("4.1.3"));
+        assert_eq!(parse_version_line("ffprobe version n6.0"), Some("n6.0"));
+        assert_eq!(parse_version_line("usage: ffmpeg"), None);
+
+        assert_eq!(version_number("4.1.3"), Some((4, 1)));
+        assert_eq!(version_number("n6.0"), Some((6, 0)));
+        assert_eq!(version_number("5.1.2-0ubuntu1"), Some((5, 1)));
+        assert_eq!(version_number("7"), Some((7, 0)));
+        assert_eq!(version_number("N-109468-gd39b34128d"), None);
+        assert!(version_number("3.4.8").unwrap() < MIN_TOOL_VERSION);
+    }
+
+    #[test]
+    fn test_locate_tool() {
+        let dir = Path::new("tests").join("output").join("locate_tool");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file_name = format!("ffprobe{}", std::env::consts::EXE_SUFFIX);
+        std::fs::write(dir.join(&file_name), "").unwrap();
+        let search_dirs = vec![dir.join("missing"), dir.clone()];
+
+        assert_eq!(
+            locate_tool("ffprobe", None, &search_dirs),
+            Ok(dir.join(&file_name))
+        );
+        assert!(locate_tool("ffmpeg", None, &search_dirs).is_err());
+        assert!(locate_tool("ffprobe", Some(&dir.join("other.exe")), &search_dirs).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_cut_job_stream_copy() {
+        let job = cut_job(
+            Path::new("ffprobe"),
+            "cut".to_owned(),
+            Path::new("game.mp4"),
+            1500,
+            4000,
+            Path::new("game_00001500.mp4"),
+            &CutSettings::default(),
+        );
+        assert_eq!(job.steps.len(), 1);
+        let args: Vec<&str> = job.steps[0]
+            .args
+            .iter()
+            .map(|a| a.to_str().unwrap())
+            .collect();
+        assert_eq!(
+            args,
+            vec!["-ss", "1.500", "-i", "game.mp4", "-t", "2.500", "-c", "copy"]
+        );
+        assert_eq!(job.steps[0].duration_ms, 2500);
+    }
+
+    #[test]
+    fn test_preview_jobs() {
+        let processor = FfmpegProcessor {
+            ffmpeg_path: PathBuf::from("ffmpeg"),
+            ffprobe_path: PathBuf::from("ffprobe"),
+        };
+        let settings = PreviewSettings::default();
+        let jobs = preview_jobs(
+            &processor,
+            Path::new("game.mp4"),
+            2000,
+            8000,
+            &settings,
+            Path::new("_previews"),
+            "game.mp4_00002000",
+        );
+        let args: Vec<Vec<&str>> = jobs
+            .iter()
+            .map(|job| {
+                job.steps[0]
+                    .args
+                    .iter()
+                    .map(|a| a.to_str().unwrap())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            jobs[0].steps[0].output,
+            Path::new("_previews").join("game.mp4_00002000.gif")
+        );
+        assert_eq!(
+            args[0],
+            vec![
+                "-ss",
+                "2.000",
+                "-i",
+                "game.mp4",
+                "-t",
+                "6.000",
+                "-vf",
+                "fps=10,scale=480:-1:flags=lanczos,split[a][b];[a]palettegen[p];[b][p]paletteuse",
+                "-loop",
+                "0",
+                "-an"
+            ]
+        );
+        assert_eq!(
+            jobs[1].steps[0].output,
+            Path::new("_previews").join("game.mp4_00002000_sheet.jpg")
+        );
+        // 12 frames in 6 seconds
+        assert!(args[1].contains(&"fps=2.000000,scale=400:-1,tile=4x3"));
+        assert!(jobs.iter().all(|job| job.overwrite));
+
+        let webp_settings = PreviewSettings {
+            format: "webp".parse().unwrap(),
+            ..settings
+        };
+        let job = animation_job(
+            "animate".to_owned(),
+            Path::new("game.mp4"),
+            0,
+            1000,
+            &webp_settings,
+            Path::new("game.webp"),
+        );
+        assert!(job.steps[0].args.contains(&"libwebp".into()));
+    }
 }
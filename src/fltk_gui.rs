@@ -1,5 +1,15 @@
+use std::path::PathBuf;
+
 use fltk::{prelude::*, window::DoubleWindow};
 
+use crate::action_handling::{AngleLayout, AngleView};
+use crate::ffmpeg::{CutMode, ToolCheck};
+use crate::jobs::{JobId, JobStatus, JobUpdate};
+use crate::project::{format_timecode, Clip, PlayAnnotation, Project, Reel, ReelClip, ReelOptions};
+
+/// Number of windows for the other angles of a game. Further angles are cut, but not shown.
+const ANGLE_WINDOW_COUNT: usize = 3;
+
 #[derive(Clone)]
 pub(crate) enum GuiActions {
     ChooseACMExe,
@@ -12,6 +22,495 @@ pub(crate) enum GuiActions {
     KeyEvent(fltk::enums::Key),
     SetMediaPosition(f64),
     SetProjectDirectory(String),
+    EditAnnotation,
+    SaveAnnotation,
+    ExportClips(String),
+    CancelJob,
+    SetCutMode(CutMode),
+    EditReels,
+    Reel(ReelEdit),
+}
+
+/// Changes made in the reel editor
+#[derive(Clone, Copy)]
+pub(crate) enum ReelEdit {
+    Select,
+    Filter,
+    New,
+    Delete,
+    AddClip,
+    RemoveClip,
+    MoveClipUp,
+    MoveClipDown,
+    /// The title card or caption checkbox was toggled
+    Options,
+    Render,
+}
+
+/// List of background jobs with their progress or result
+pub(crate) struct JobList {
+    browser: fltk::browser::HoldBrowser,
+    /// Id of the job shown in each line of the browser
+    jobs: Vec<JobId>,
+}
+
+impl JobList {
+    pub(crate) fn update(&mut self, update: &JobUpdate) {
+        let status = match &update.status {
+            JobStatus::Queued => "queued".to_owned(),
+            JobStatus::Running(progress) => format!("{:.0}%", progress * 100.0),
+            JobStatus::Succeeded => "done".to_owned(),
+            JobStatus::Failed(e) => format!("FAILED: {}", e.lines().last().unwrap_or("")),
+            JobStatus::Cancelled => "cancelled".to_owned(),
+        };
+        let text = format!("[{}] {}", status, update.description);
+
+        match self.jobs.iter().position(|id| *id == update.id) {
+            Some(i) => self.browser.set_text(i as i32 + 1, &text),
+            None => {
+                self.jobs.push(update.id);
+                self.browser.add(&text);
+                self.browser.bottom_line(self.jobs.len() as i32);
+            }
+        }
+    }
+
+    pub(crate) fn selected_job(&self) -> Option<JobId> {
+        let line = self.browser.value();
+        if line < 1 {
+            return None;
+        }
+        self.jobs.get(line as usize - 1).copied()
+    }
+}
+
+/// Window to edit the play annotation of a clip
+pub(crate) struct AnnotationForm {
+    win: DoubleWindow,
+    down_input: fltk::input::IntInput,
+    distance_input: fltk::input::IntInput,
+    field_position_input: fltk::input::Input,
+    formation_input: fltk::input::Input,
+    play_call_input: fltk::input::Input,
+    result_yards_input: fltk::input::IntInput,
+    note_input: fltk::input::MultilineInput,
+    error_label: fltk::frame::Frame,
+    /// Video and start of the clip that is edited
+    pub(crate) clip: Option<(PathBuf, i64)>,
+}
+
+impl AnnotationForm {
+    fn new(s: fltk::app::Sender<GuiActions>) -> AnnotationForm {
+        let label_width = 120;
+        let input_width = 300;
+        let row_height = 30;
+        let win = fltk::window::Window::new(
+            100,
+            100,
+            label_width + input_width + 20,
+            row_height * 10,
+            "Play annotation",
+        );
+
+        let row = |i: i32| 10 + i * row_height;
+        let down_input =
+            fltk::input::IntInput::new(label_width, row(0), 50, row_height - 5, "Down");
+        let distance_input =
+            fltk::input::IntInput::new(label_width, row(1), 50, row_height - 5, "Distance");
+        let field_position_input = fltk::input::Input::new(
+            label_width,
+            row(2),
+            input_width,
+            row_height - 5,
+            "Field position",
+        );
+        let formation_input = fltk::input::Input::new(
+            label_width,
+            row(3),
+            input_width,
+            row_height - 5,
+            "Formation",
+        );
+        let play_call_input = fltk::input::Input::new(
+            label_width,
+            row(4),
+            input_width,
+            row_height - 5,
+            "Play call",
+        );
+        let result_yards_input =
+            fltk::input::IntInput::new(label_width, row(5), 50, row_height - 5, "Result (yds)");
+        let note_input = fltk::input::MultilineInput::new(
+            label_width,
+            row(6),
+            input_width,
+            row_height * 2 - 5,
+            "Note",
+        );
+
+        let mut save_button =
+            fltk::button::Button::new(label_width, row(8), 100, row_height - 5, "Save");
+        save_button.emit(s, GuiActions::SaveAnnotation);
+
+        let mut error_label = fltk::frame::Frame::new(
+            10,
+            row(9) - 5,
+            label_width + input_width,
+            row_height - 5,
+            None,
+        );
+        error_label.set_label_color(fltk::enums::Color::Red);
+
+        let mut cancel_button =
+            fltk::button::Button::new(label_width + 110, row(8), 100, row_height - 5, "Cancel");
+        let mut win_clone = win.clone();
+        cancel_button.set_callback(move |_widget| win_clone.hide());
+
+        win.end();
+
+        AnnotationForm {
+            win,
+            down_input,
+            distance_input,
+            field_position_input,
+            formation_input,
+            play_call_input,
+            result_yards_input,
+            note_input,
+            error_label,
+            clip: None,
+        }
+    }
+
+    /// Opens the form for the clip starting at `start` in the video at `media_path`
+    pub(crate) fn show(&mut self, media_path: PathBuf, start: i64, annotation: &PlayAnnotation) {
+        fn optional_to_string<T: ToString>(value: Option<T>) -> String {
+            value.map(|v| v.to_string()).unwrap_or_default()
+        }
+
+        self.win.set_label(&format!(
+            "Play at {} in {}",
+            crate::project::format_timecode(start),
+            media_path.file_name().unwrap().to_string_lossy()
+        ));
+        self.down_input
+            .set_value(&optional_to_string(annotation.down));
+        self.distance_input
+            .set_value(&optional_to_string(annotation.distance));
+        self.field_position_input
+            .set_value(&annotation.field_position);
+        self.formation_input.set_value(&annotation.formation);
+        self.play_call_input.set_value(&annotation.play_call);
+        self.result_yards_input
+            .set_value(&optional_to_string(annotation.result_yards));
+        self.note_input.set_value(&annotation.note);
+        self.error_label.set_label("");
+        self.clip = Some((media_path, start));
+        self.win.show();
+    }
+
+    pub(crate) fn hide(&mut self) {
+        self.clip = None;
+        self.win.hide();
+    }
+
+    /// Reads the annotation from the form, or an error if a number field is invalid
+    pub(crate) fn annotation(&self) -> Result<PlayAnnotation, String> {
+        PlayAnnotation::from_fields(
+            &self.down_input.value(),
+            &self.distance_input.value(),
+            &self.field_position_input.value(),
+            &self.formation_input.value(),
+            &self.play_call_input.value(),
+            &self.result_yards_input.value(),
+            &self.note_input.value(),
+        )
+    }
+
+    /// Shows why the annotation could not be saved, keeping the form open
+    pub(crate) fn show_error(&mut self, message: &str) {
+        self.error_label.set_label(message);
+        self.win.redraw();
+    }
+}
+
+/// Describes a clip in the lists of the reel editor, e.g.
+/// `game1.mp4 0:01:02.300 [Offense] 3rd & 7, Trips Right, Slant`
+fn reel_clip_label(reel_clip: &ReelClip, clip: Option<&Clip>) -> String {
+    let mut label = format!("{} {}", reel_clip.media, format_timecode(reel_clip.start));
+    let clip = match clip {
+        Some(clip) => clip,
+        None => return label + " (deleted)",
+    };
+    if !clip.tags.is_empty() {
+        label += &format!(" [{}]", clip.tags.join(", "));
+    }
+
+    let annotation = &clip.annotation;
+    let mut details: Vec<String> = annotation.down_and_distance().into_iter().collect();
+    details.extend(
+        [
+            &annotation.field_position,
+            &annotation.formation,
+            &annotation.play_call,
+            &annotation.note,
+        ]
+        .iter()
+        .filter(|text| !text.is_empty())
+        .map(|text| text.replace('\n', " ")),
+    );
+    if !details.is_empty() {
+        label += " ";
+        label += &details.join(", ");
+    }
+    label
+}
+
+/// Window to compose reels out of the clips of the project
+pub(crate) struct ReelEditor {
+    win: fltk::window::Window,
+    reel_browser: fltk::browser::HoldBrowser,
+    name_input: fltk::input::Input,
+    filter_input: fltk::input::Input,
+    clip_browser: fltk::browser::HoldBrowser,
+    reel_clip_browser: fltk::browser::HoldBrowser,
+    title_cards_button: fltk::button::CheckButton,
+    captions_button: fltk::button::CheckButton,
+    /// Copy of the project's reels that is edited
+    reels: Vec<Reel>,
+    /// Clips shown in `clip_browser`
+    clips: Vec<ReelClip>,
+}
+
+impl ReelEditor {
+    fn new(s: fltk::app::Sender<GuiActions>) -> ReelEditor {
+        let win = fltk::window::Window::new(100, 100, 1000, 470, "Reels");
+        let reel_action = |widget: &mut fltk::button::Button, edit: ReelEdit| {
+            widget.emit(s.clone(), GuiActions::Reel(edit));
+        };
+
+        let mut reel_browser = fltk::browser::HoldBrowser::new(10, 10, 250, 150, None);
+        reel_browser.emit(s.clone(), GuiActions::Reel(ReelEdit::Select));
+        let name_input = fltk::input::Input::new(10, 170, 250, 25, None);
+        let mut new_button = fltk::button::Button::new(10, 200, 120, 25, "New reel");
+        reel_action(&mut new_button, ReelEdit::New);
+        let mut delete_button = fltk::button::Button::new(140, 200, 120, 25, "Delete reel");
+        reel_action(&mut delete_button, ReelEdit::Delete);
+        let mut render_button = fltk::button::Button::new(10, 240, 250, 30, "Render reel");
+        reel_action(&mut render_button, ReelEdit::Render);
+        let mut title_cards_button =
+            fltk::button::CheckButton::new(10, 280, 250, 25, "Title cards");
+        title_cards_button.set_tooltip("Show the tags and annotation before every clip");
+        title_cards_button.emit(s.clone(), GuiActions::Reel(ReelEdit::Options));
+        let mut captions_button = fltk::button::CheckButton::new(10, 310, 250, 25, "Captions");
+        captions_button.set_tooltip("Burn the tags and annotation into the bottom of every clip");
+        captions_button.emit(s.clone(), GuiActions::Reel(ReelEdit::Options));
+
+        let mut filter_input = fltk::input::Input::new(270, 10, 330, 25, None);
+        filter_input.set_tooltip("Show only clips containing all these words, e.g. \"3rd cover\"");
+        filter_input.set_trigger(fltk::enums::CallbackTrigger::Changed);
+        filter_input.emit(s.clone(), GuiActions::Reel(ReelEdit::Filter));
+        let clip_browser = fltk::browser::HoldBrowser::new(270, 40, 330, 420, None);
+
+        let mut add_button = fltk::button::Button::new(610, 180, 80, 30, "Add >");
+        reel_action(&mut add_button, ReelEdit::AddClip);
+        let mut remove_button = fltk::button::Button::new(610, 220, 80, 30, "< Remove");
+        reel_action(&mut remove_button, ReelEdit::RemoveClip);
+
+        let reel_clip_browser = fltk::browser::HoldBrowser::new(700, 10, 290, 420, None);
+        let mut up_button = fltk::button::Button::new(700, 435, 80, 25, "Up");
+        reel_action(&mut up_button, ReelEdit::MoveClipUp);
+        let mut down_button = fltk::button::Button::new(790, 435, 80, 25, "Down");
+        reel_action(&mut down_button, ReelEdit::MoveClipDown);
+
+        win.end();
+
+        ReelEditor {
+            win,
+            reel_browser,
+            name_input,
+            filter_input,
+            clip_browser,
+            reel_clip_browser,
+            title_cards_button,
+            captions_button,
+            reels: Vec::new(),
+            clips: Vec::new(),
+        }
+    }
+
+    pub(crate) fn show(&mut self, project: &Project) {
+        self.reels = project.reels().to_vec();
+        self.show_reels(project, if self.reels.is_empty() { 0 } else { 1 });
+        self.filter_clips(project);
+        self.win.show();
+    }
+
+    pub(crate) fn hide(&mut self) {
+        self.win.hide();
+    }
+
+    /// The reel selected in the list of reels
+    pub(crate) fn selected_reel(&self) -> Option<&Reel> {
+        self.reels.get((self.reel_browser.value() - 1) as usize)
+    }
+
+    /// The edited reels
+    pub(crate) fn reels(&self) -> &[Reel] {
+        &self.reels
+    }
+
+    /// Applies an edit to the reels. Returns whether the reels changed.
+    pub(crate) fn apply(&mut self, edit: ReelEdit, project: &Project) -> bool {
+        let reel_line = self.reel_browser.value();
+        let clip_line = self.reel_clip_browser.value();
+        let reel_index = (reel_line - 1) as usize;
+        let clip_index = (clip_line - 1) as usize;
+        match edit {
+            ReelEdit::Select => {
+                self.show_reel_clips(project, 0);
+                false
+            }
+            ReelEdit::Filter => {
+                self.filter_clips(project);
+                false
+            }
+            ReelEdit::New => {
+                let name = self.name_input.value().trim().to_owned();
+                if name.is_empty() || self.reels.iter().any(|reel| reel.name == name) {
+                    fltk::dialog::alert_default("Enter a name that no other reel has");
+                    return false;
+                }
+                self.reels.push(Reel {
+                    name,
+                    clips: Vec::new(),
+                    options: ReelOptions::default(),
+                });
+                self.name_input.set_value("");
+                self.show_reels(project, self.reels.len() as i32);
+                true
+            }
+            ReelEdit::Delete => {
+                if reel_line < 1 {
+                    return false;
+                }
+                self.reels.remove(reel_index);
+                self.show_reels(project, reel_line.min(self.reels.len() as i32));
+                true
+            }
+            ReelEdit::AddClip => {
+                let clip = match self.clips.get((self.clip_browser.value() - 1) as usize) {
+                    Some(clip) => clip.clone(),
+                    None => return false,
+                };
+                let reel = match self.reels.get_mut(reel_index) {
+                    Some(reel) => reel,
+                    None => return false,
+                };
+                // behind the selected clip, so a reel can be built in any order
+                let position = if clip_line < 1 {
+                    reel.clips.len()
+                } else {
+                    clip_index + 1
+                };
+                reel.clips.insert(position, clip);
+                self.show_reel_clips(project, position as i32 + 1);
+                true
+            }
+            ReelEdit::RemoveClip => match self.reels.get_mut(reel_index) {
+                Some(reel) if clip_index < reel.clips.len() => {
+                    reel.clips.remove(clip_index);
+                    let line = clip_line.min(reel.clips.len() as i32);
+                    self.show_reel_clips(project, line);
+                    true
+                }
+                _ => false,
+            },
+            ReelEdit::MoveClipUp | ReelEdit::MoveClipDown => {
+                if clip_line < 1 {
+                    return false;
+                }
+                let reel = match self.reels.get_mut(reel_index) {
+                    Some(reel) => reel,
+                    None => return false,
+                };
+                let other_index = match edit {
+                    ReelEdit::MoveClipUp => clip_index.checked_sub(1),
+                    _ => Some(clip_index + 1),
+                };
+                match other_index {
+                    Some(other_index)
+                        if clip_index < reel.clips.len() && other_index < reel.clips.len() =>
+                    {
+                        reel.clips.swap(clip_index, other_index);
+                        self.show_reel_clips(project, other_index as i32 + 1);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            ReelEdit::Options => match self.reels.get_mut(reel_index) {
+                Some(reel) => {
+                    reel.options.title_cards = self.title_cards_button.value();
+                    reel.options.captions = self.captions_button.value();
+                    true
+                }
+                None => false,
+            },
+            ReelEdit::Render => false,
+        }
+    }
+
+    /// Lists the reels and selects the given line (starting at 1)
+    fn show_reels(&mut self, project: &Project, selected_line: i32) {
+        self.reel_browser.clear();
+        for reel in self.reels.iter() {
+            self.reel_browser
+                .add(&format!("@.{} ({} clips)", reel.name, reel.clips.len()));
+        }
+        if selected_line > 0 {
+            self.reel_browser.select(selected_line);
+        }
+        self.show_reel_clips(project, 0);
+    }
+
+    /// Lists the clips of the selected reel and selects the given line (starting at 1)
+    fn show_reel_clips(&mut self, project: &Project, selected_line: i32) {
+        self.reel_clip_browser.clear();
+        let reel = match self.reels.get((self.reel_browser.value() - 1) as usize) {
+            Some(reel) => reel,
+            None => {
+                self.title_cards_button.set_value(false);
+                self.captions_button.set_value(false);
+                return;
+            }
+        };
+        self.title_cards_button.set_value(reel.options.title_cards);
+        self.captions_button.set_value(reel.options.captions);
+        for reel_clip in reel.clips.iter() {
+            let label = reel_clip_label(reel_clip, project.reel_clip(reel_clip));
+            self.reel_clip_browser.add(&format!("@.{}", label));
+        }
+        if selected_line > 0 {
+            self.reel_clip_browser.select(selected_line);
+        }
+    }
+
+    /// Lists the clips of the project that match the filter
+    fn filter_clips(&mut self, project: &Project) {
+        let filter = self.filter_input.value().to_lowercase();
+        let words: Vec<&str> = filter.split_whitespace().collect();
+        self.clip_browser.clear();
+        self.clips.clear();
+        for (reel_clip, clip) in project.reel_clips() {
+            let label = reel_clip_label(&reel_clip, Some(clip));
+            let lowercase_label = label.to_lowercase();
+            if words.iter().all(|word| lowercase_label.contains(word)) {
+                self.clip_browser.add(&format!("@.{}", label));
+                self.clips.push(reel_clip);
+            }
+        }
+    }
 }
 
 pub(crate) struct FltkGui {
@@ -24,6 +523,91 @@ pub(crate) struct FltkGui {
     pub(crate) sensitivity_input: fltk::input::FloatInput,
     pub(crate) slider: fltk::valuator::HorNiceSlider,
     pub(crate) vlc_win: DoubleWindow,
+    /// Windows for the other angles of the current video
+    pub(crate) angle_wins: Vec<DoubleWindow>,
+    /// The space of the main window that the video windows share
+    video_area: fltk::frame::Frame,
+    rate_display: fltk::frame::Frame,
+    review_display: fltk::frame::Frame,
+    pub(crate) annotation_form: AnnotationForm,
+    pub(crate) job_list: JobList,
+    pub(crate) cut_mode_choice: fltk::menu::Choice,
+    pub(crate) reel_editor: ReelEditor,
+    tool_status: fltk::frame::Frame,
+}
+
+impl FltkGui {
+    /// Shows the cut mode of a newly opened project
+    pub(crate) fn show_cut_mode(&mut self, mode: CutMode) {
+        if let Some(i) = CutMode::ALL.iter().position(|m| *m == mode) {
+            self.cut_mode_choice.set_value(i as i32);
+        }
+    }
+
+    /// Shows whether ffmpeg and ffprobe were found, in red if one of them cannot be used
+    pub(crate) fn show_tool_status(&mut self, checks: &[ToolCheck]) {
+        let labels: Vec<String> = checks.iter().map(|check| check.to_string()).collect();
+        self.tool_status.set_label(&labels.join("   "));
+        if checks.iter().any(|check| check.problem.is_some()) {
+            self.tool_status.set_label_color(fltk::enums::Color::Red);
+        } else {
+            self.tool_status.set_label_color(fltk::enums::Color::Black);
+        }
+        self.tool_status.redraw();
+    }
+
+    /// Shows the playback rate, e.g. "1.5x". Only redraws if the rate changed.
+    pub(crate) fn show_rate(&mut self, rate: f32) {
+        let label = format!("{}x", (rate * 100.0).round() / 100.0);
+        if self.rate_display.label() != label {
+            self.rate_display.set_label(&label);
+            self.rate_display.redraw();
+        }
+    }
+
+    /// Shows which clip is played while reviewing clips, e.g. "clip 7 / 42".
+    /// Only redraws if the clip changed.
+    pub(crate) fn show_review(&mut self, position: Option<(usize, usize)>) {
+        let label = match position {
+            Some((current, count)) => format!("clip {} / {}", current + 1, count),
+            None => String::new(),
+        };
+        if self.review_display.label() != label {
+            self.review_display.set_label(&label);
+            self.review_display.redraw();
+        }
+    }
+
+    /// Arranges the video windows for the angles of the current video: the active angle fills
+    /// the video area or all angles share it in two columns. Unused windows are moved out of
+    /// sight instead of being hidden, because hiding destroys the native windows VLC draws into.
+    pub(crate) fn show_angles(&mut self, view: &AngleView) {
+        let (x, y, w, h) = (
+            self.video_area.x(),
+            self.video_area.y(),
+            self.video_area.w(),
+            self.video_area.h(),
+        );
+        let columns = if view.count > 1 { 2 } else { 1 };
+        let rows = (view.count as i32 + 1) / 2;
+        let windows = std::iter::once(&mut self.vlc_win).chain(self.angle_wins.iter_mut());
+        for (i, win) in windows.enumerate() {
+            match view.layout {
+                AngleLayout::Single if i == view.active => win.resize(x, y, w, h),
+                AngleLayout::SideBySide if i < view.count => {
+                    let (column, row) = (i as i32 % columns, i as i32 / columns);
+                    win.resize(
+                        x + column * w / columns,
+                        y + row * h / rows,
+                        w / columns,
+                        h / rows,
+                    )
+                }
+                _ => win.resize(-1, -1, 1, 1),
+            }
+            win.redraw();
+        }
+    }
 }
 
 impl FltkGui {
@@ -48,6 +632,21 @@ impl FltkGui {
         );
         vlc_win.end();
         vlc_win.set_color(fltk::enums::Color::Black);
+        let video_area = fltk::frame::Frame::new(
+            vlc_win.x(),
+            vlc_win.y(),
+            vlc_win.width(),
+            vlc_win.height(),
+            None,
+        );
+        let angle_wins: Vec<DoubleWindow> = (0..ANGLE_WINDOW_COUNT)
+            .map(|_| {
+                let mut angle_win = fltk::window::Window::new(-1, -1, 1, 1, "");
+                angle_win.end();
+                angle_win.set_color(fltk::enums::Color::Black);
+                angle_win
+            })
+            .collect();
 
         let gui_elements_start_x = vlc_vertical_margin;
         let gui_elements_start_y = vlc_win.y() + vlc_win.height() + 10;
@@ -66,6 +665,14 @@ impl FltkGui {
             fltk::button::Button::new(110, gui_elements_start_y + 50, 100, 30, "set end frame");
         end_frame_button.emit(s.clone(), GuiActions::SetEndFrame);
 
+        let mut rate_display =
+            fltk::frame::Frame::new(215, gui_elements_start_y + 20, 80, 30, "1x");
+        rate_display.set_tooltip("Playback speed");
+
+        let mut review_display =
+            fltk::frame::Frame::new(215, gui_elements_start_y + 50, 90, 30, None);
+        review_display.set_tooltip("Reviewed clip");
+
         let mut slider = fltk::valuator::HorNiceSlider::new(
             gui_elements_start_x,
             gui_elements_start_y,
@@ -153,11 +760,96 @@ impl FltkGui {
             }
         });
 
+        let mut cut_mode_choice = fltk::menu::Choice::new(
+            gui_elements_start_x + 980,
+            gui_elements_start_y + 20,
+            150,
+            25,
+            None,
+        );
+        cut_mode_choice.set_tooltip("How clips are cut");
+        for mode in CutMode::ALL.iter() {
+            cut_mode_choice.add_choice(mode.label());
+        }
+        cut_mode_choice.set_value(0);
+        let s_clone = s.clone();
+        cut_mode_choice.set_callback(move |choice| {
+            if let Some(mode) = CutMode::ALL.get(choice.value() as usize) {
+                s_clone.send(GuiActions::SetCutMode(*mode));
+            }
+        });
+
+        let mut annotate_button = fltk::button::Button::new(
+            gui_elements_start_x + 1140,
+            gui_elements_start_y + 50,
+            150,
+            30,
+            "Annotate play",
+        );
+        annotate_button.emit(s.clone(), GuiActions::EditAnnotation);
+
+        let mut export_button = fltk::button::Button::new(
+            gui_elements_start_x + 1140,
+            gui_elements_start_y + 20,
+            150,
+            30,
+            "Export clips..",
+        );
+        let s_clone = s.clone();
+        export_button.set_callback(move |_widget| {
+            let mut export_chooser =
+                fltk::dialog::FileDialog::new(fltk::dialog::FileDialogType::BrowseSaveFile);
+            export_chooser.set_title("Export clips as CSV or JSON");
+            export_chooser.set_filter("CSV\t*.csv\nJSON\t*.json");
+            export_chooser.show();
+            let path = export_chooser.filename();
+            if !path.as_os_str().is_empty() {
+                s_clone.send(GuiActions::ExportClips(path.to_string_lossy().into_owned()));
+            }
+        });
+
+        let mut reels_button = fltk::button::Button::new(
+            gui_elements_start_x + 1140,
+            gui_elements_start_y + 85,
+            150,
+            30,
+            "Edit reels..",
+        );
+        reels_button.emit(s.clone(), GuiActions::EditReels);
+
+        let job_browser = fltk::browser::HoldBrowser::new(
+            gui_elements_start_x + 1300,
+            gui_elements_start_y + 20,
+            vlc_win_width - 1300,
+            90,
+            None,
+        );
+        let mut cancel_job_button = fltk::button::Button::new(
+            gui_elements_start_x + 1300,
+            gui_elements_start_y + 112,
+            120,
+            25,
+            "Cancel job",
+        );
+        cancel_job_button.emit(s.clone(), GuiActions::CancelJob);
+
+        let mut tool_status = fltk::frame::Frame::new(
+            gui_elements_start_x,
+            gui_elements_start_y + 95,
+            960,
+            25,
+            None,
+        );
+        tool_status.set_align(fltk::enums::Align::Left | fltk::enums::Align::Inside);
+
         win.make_resizable(true);
         //win.fullscreen(true);
         win.end();
         win.show();
 
+        let annotation_form = AnnotationForm::new(s.clone());
+        let reel_editor = ReelEditor::new(s.clone());
+
         //let (key_event_sender, key_event_receiver) = fltk::app::channel::<fltk::enums::Key>();
         win.handle(move |_w, ev| match ev {
             fltk::enums::Event::NoEvent => false, // happens on windows according to: https://docs.rs/fltk/1.2.3/fltk/app/fn.wait_for.html
@@ -187,6 +879,18 @@ impl FltkGui {
             sensitivity_input,
             slider,
             vlc_win,
+            angle_wins,
+            video_area,
+            rate_display,
+            review_display,
+            annotation_form,
+            job_list: JobList {
+                browser: job_browser,
+                jobs: Vec::new(),
+            },
+            cut_mode_choice,
+            reel_editor,
+            tool_status,
         }
     }
 }
@@ -1,10 +1,18 @@
 use gilrs::{Button, Event, EventType, Gilrs};
 
 use super::super::Action;
-use super::super::ClipType;
+use super::gamepad_map::{format_uuid, AnalogAction, AxisAction, GamepadMapping};
+use crate::settings::GamepadSettings;
 use std::time::Duration;
 use std::time::Instant;
 
+/// A thumbstick has to be tilted further than this to jog
+const JOG_THRESHOLD: f32 = 0.3;
+/// Time between the frame steps of a thumbstick that is tilted just past `JOG_THRESHOLD`
+const JOG_SLOWEST_INTERVAL: Duration = Duration::from_millis(400);
+/// Time between the frame steps of a fully tilted thumbstick
+const JOG_FASTEST_INTERVAL: Duration = Duration::from_millis(40);
+
 struct LastPressed {
     btn: Button,
     pressed_time: Instant,
@@ -16,58 +24,68 @@ impl LastPressed {
     }
 }
 
+/// A thumbstick that is tilted to step frames
+struct Jog {
+    forward: bool,
+    tilt: f32,
+    last_step: Instant,
+}
+
+impl Jog {
+    fn step(&mut self) -> Action {
+        self.last_step = Instant::now();
+        if self.forward {
+            Action::NextFrame
+        } else {
+            Action::PreviousFrame
+        }
+    }
+
+    fn interval(&self) -> Duration {
+        let share = ((self.tilt - JOG_THRESHOLD) / (1.0 - JOG_THRESHOLD)).clamp(0.0, 1.0);
+        JOG_SLOWEST_INTERVAL - (JOG_SLOWEST_INTERVAL - JOG_FASTEST_INTERVAL).mul_f32(share)
+    }
+}
+
 pub(crate) struct Controller {
     engine: Gilrs,
+    mapping: GamepadMapping,
+    settings: GamepadSettings,
     last_pressed: Option<LastPressed>,
+    /// The analog button that has been scrubbing the video since it was pressed
+    shuttle: Option<Button>,
+    jog: Option<Jog>,
 }
 
 impl Controller {
-    pub fn new() -> Controller {
+    pub fn new(settings: GamepadSettings) -> Controller {
         let gilrs = Gilrs::new().unwrap();
 
         println!("list gamepads:");
         for (_id, gamepad) in gilrs.gamepads() {
-            println!("{} is {:?}", gamepad.name(), gamepad.power_info());
+            println!(
+                "{} (uuid {}) is {:?}",
+                gamepad.name(),
+                format_uuid(&gamepad.uuid()),
+                gamepad.power_info()
+            );
         }
 
         Controller {
             engine: gilrs,
+            mapping: GamepadMapping::load(),
+            settings,
             last_pressed: None,
-        }
-    }
-
-    fn long_press_map(btn: Button) -> Option<Action> {
-        match btn {
-            Button::DPadLeft => Some(Action::PreviousMedia),
-            Button::DPadRight => Some(Action::NextMedia),
-            Button::South => Some(Action::BreakLoop),
-            Button::West => Some(Action::PreviousCutmark),
-            Button::East => Some(Action::NextCutmark),
-            Button::North => Some(Action::ConcatClips),
-            _ => None,
-        }
-    }
-
-    fn short_press_map(btn: Button) -> Option<Action> {
-        match btn {
-            Button::Start => Some(Action::Exit),
-            Button::South => Some(Action::TogglePlayPause),
-            Button::West => Some(Action::StartLoop),
-            Button::East => Some(Action::EndLoop),
-            Button::North => Some(Action::CutCurrentLoop(None)),
-            Button::LeftTrigger => Some(Action::DecreaseSpeed),
-            Button::RightTrigger => Some(Action::IncreaseSpeed),
-            Button::DPadRight => Some(Action::NextClip),
-            Button::DPadLeft => Some(Action::PreviousClip),
-            Button::DPadUp => Some(Action::CutCurrentLoop(Some(ClipType::Offense))),
-            Button::DPadDown => Some(Action::CutCurrentLoop(Some(ClipType::Defense))),
-            _ => None,
+            shuttle: None,
+            jog: None,
         }
     }
 
     pub fn next_action(&mut self) -> Option<Action> {
         if let Some(Event { id, event, time }) = self.engine.next_event() {
             dbg!("{:?} New event from {}: {:?}", time, id, event);
+            let gamepad = self.engine.gamepad(id);
+            let button_map = self.mapping.button_map(gamepad.name(), &gamepad.uuid());
             match event {
                 EventType::ButtonPressed(btn, _) => {
                     self.last_pressed = Some(LastPressed {
@@ -77,25 +95,72 @@ impl Controller {
                     None
                 }
 
+                // a trigger that has been scrubbing does not trigger its short press action
+                EventType::ButtonReleased(btn, _) if self.shuttle == Some(btn) => {
+                    self.shuttle = None;
+                    Some(Action::Shuttle(0.0))
+                }
+
                 EventType::ButtonReleased(btn, _) => match &self.last_pressed {
                     Some(x) if x.has_been_pressed_within(btn, Duration::from_millis(500)) => {
-                        Controller::long_press_map(btn)
+                        button_map.long_press(btn)
                     }
-                    _ => Controller::short_press_map(btn),
+                    _ => button_map.short_press(btn),
                 },
 
-                EventType::ButtonChanged(btn, pos, _) => match btn {
-                    Button::LeftTrigger => Some(Action::Rewind(pos)),
-                    Button::LeftTrigger2 => Some(Action::Rewind(pos)),
-                    Button::RightTrigger => Some(Action::Forward(pos)),
-                    Button::RightTrigger2 => Some(Action::Forward(pos)),
-                    _ => None,
+                EventType::ButtonChanged(btn, pos, _) => {
+                    let direction = match button_map.analog(btn)? {
+                        AnalogAction::Rewind => -1.0,
+                        AnalogAction::Forward => 1.0,
+                    };
+                    let speed = self.settings.shuttle_speed(pos);
+                    if speed > 0.0 {
+                        self.shuttle = Some(btn);
+                        Some(Action::Shuttle(direction * speed))
+                    } else if self.shuttle == Some(btn) {
+                        Some(Action::Shuttle(0.0))
+                    } else {
+                        None
+                    }
+                }
+
+                EventType::AxisChanged(axis, value, _) => match button_map.axis(axis)? {
+                    AxisAction::Jog => self.jog(value),
                 },
 
                 _ => None,
             }
         } else {
-            None
+            match &mut self.jog {
+                Some(jog) if jog.last_step.elapsed() >= jog.interval() => Some(jog.step()),
+                _ => None,
+            }
+        }
+    }
+
+    /// Steps a frame when the thumbstick is tilted to a new direction. While it stays tilted,
+    /// the following frames are stepped by `next_action`.
+    fn jog(&mut self, value: f32) -> Option<Action> {
+        if value.abs() < JOG_THRESHOLD {
+            self.jog = None;
+            return None;
+        }
+        let forward = value > 0.0;
+        match &mut self.jog {
+            Some(jog) if jog.forward == forward => {
+                jog.tilt = value.abs();
+                None
+            }
+            _ => {
+                let mut jog = Jog {
+                    forward,
+                    tilt: value.abs(),
+                    last_step: Instant::now(),
+                };
+                let action = jog.step();
+                self.jog = Some(jog);
+                Some(action)
+            }
         }
     }
 }
@@ -0,0 +1,509 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use gilrs::{Axis, Button};
+use serde::Deserialize;
+
+use crate::Action;
+
+const GAMEPAD_MAP_FILE_NAME: &str = "gamepad.toml";
+
+type RawSection = Option<BTreeMap<String, String>>;
+
+/// Layout of gamepad.toml. The top-level sections are the default mapping,
+/// every `[[profile]]` overrides single buttons of it for the gamepads it matches.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawGamepadMapFile {
+    short_press: RawSection,
+    long_press: RawSection,
+    analog: RawSection,
+    axis: RawSection,
+    #[serde(default)]
+    profile: Vec<RawProfile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawProfile {
+    name: Option<String>,
+    uuid: Option<String>,
+    short_press: RawSection,
+    long_press: RawSection,
+    analog: RawSection,
+    axis: RawSection,
+}
+
+/// Actions that can be controlled by how far an analog button is pressed.
+/// While the button is held, the video is scrubbed in the direction of the action.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum AnalogAction {
+    Rewind,
+    Forward,
+}
+
+/// Actions that can be controlled by tilting a thumbstick
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum AxisAction {
+    /// Steps frames forward or backward while the stick is tilted
+    Jog,
+}
+
+/// Actions of the buttons of one gamepad
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ButtonMap {
+    short_press: HashMap<Button, Action>,
+    long_press: HashMap<Button, Action>,
+    analog: HashMap<Button, AnalogAction>,
+    axis: HashMap<Axis, AxisAction>,
+}
+
+impl ButtonMap {
+    /// The Xbox layout VAC was originally written for
+    fn default_map() -> ButtonMap {
+        let short_press = vec![
+            (Button::Start, Action::Exit),
+            (Button::South, Action::TogglePlayPause),
+            (Button::West, Action::StartLoop),
+            (Button::East, Action::EndLoop),
+            (Button::North, Action::CutCurrentLoop(Vec::new())),
+            (Button::LeftTrigger, Action::DecreaseSpeed),
+            (Button::RightTrigger, Action::IncreaseSpeed),
+            (Button::Select, Action::ResetSpeed),
+            (Button::RightThumb, Action::NextFrame),
+            (Button::LeftThumb, Action::PreviousFrame),
+            (Button::DPadRight, Action::NextClip),
+            (Button::DPadLeft, Action::PreviousClip),
+            (
+                Button::DPadUp,
+                Action::CutCurrentLoop(vec!["Offense".to_owned()]),
+            ),
+            (
+                Button::DPadDown,
+                Action::CutCurrentLoop(vec!["Defense".to_owned()]),
+            ),
+        ];
+
+        let long_press = vec![
+            (Button::DPadLeft, Action::PreviousMedia),
+            (Button::DPadRight, Action::NextMedia),
+            (Button::South, Action::BreakLoop),
+            (Button::West, Action::PreviousCutmark),
+            (Button::East, Action::NextCutmark),
+            (Button::North, Action::ConcatClips),
+            (Button::LeftThumb, Action::PlayReverse),
+            (Button::Select, Action::CycleLoopOptions),
+        ];
+
+        let analog = vec![
+            (Button::LeftTrigger2, AnalogAction::Rewind),
+            (Button::RightTrigger2, AnalogAction::Forward),
+        ];
+
+        let axis = vec![(Axis::RightStickX, AxisAction::Jog)];
+
+        ButtonMap {
+            short_press: short_press.into_iter().collect(),
+            long_press: long_press.into_iter().collect(),
+            analog: analog.into_iter().collect(),
+            axis: axis.into_iter().collect(),
+        }
+    }
+
+    pub(crate) fn short_press(&self, btn: Button) -> Option<Action> {
+        self.short_press.get(&btn).cloned()
+    }
+
+    pub(crate) fn long_press(&self, btn: Button) -> Option<Action> {
+        self.long_press.get(&btn).cloned()
+    }
+
+    pub(crate) fn analog(&self, btn: Button) -> Option<AnalogAction> {
+        self.analog.get(&btn).copied()
+    }
+
+    pub(crate) fn axis(&self, axis: Axis) -> Option<AxisAction> {
+        self.axis.get(&axis).copied()
+    }
+}
+
+struct Profile {
+    name: Option<String>,
+    uuid: Option<String>,
+    map: ButtonMap,
+}
+
+impl Profile {
+    fn matches(&self, gamepad_name: &str, gamepad_uuid: &str) -> bool {
+        self.name.as_deref() == Some(gamepad_name)
+            || self.uuid.as_deref().map(normalize_uuid).as_deref() == Some(gamepad_uuid)
+    }
+}
+
+/// Button mappings of all gamepads, loaded from gamepad.toml
+pub(crate) struct GamepadMapping {
+    default: ButtonMap,
+    profiles: Vec<Profile>,
+}
+
+/// UUIDs are compared as 32 lowercase hex digits, so they may be written with or without dashes
+fn normalize_uuid(uuid: &str) -> String {
+    uuid.chars()
+        .filter(|c| *c != '-')
+        .collect::<String>()
+        .to_lowercase()
+}
+
+pub(crate) fn format_uuid(uuid: &[u8; 16]) -> String {
+    uuid.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn parse_button(name: &str) -> Option<Button> {
+    let btn = match name {
+        "South" => Button::South,
+        "East" => Button::East,
+        "North" => Button::North,
+        "West" => Button::West,
+        "C" => Button::C,
+        "Z" => Button::Z,
+        "LeftTrigger" => Button::LeftTrigger,
+        "LeftTrigger2" => Button::LeftTrigger2,
+        "RightTrigger" => Button::RightTrigger,
+        "RightTrigger2" => Button::RightTrigger2,
+        "Select" => Button::Select,
+        "Start" => Button::Start,
+        "Mode" => Button::Mode,
+        "LeftThumb" => Button::LeftThumb,
+        "RightThumb" => Button::RightThumb,
+        "DPadUp" => Button::DPadUp,
+        "DPadDown" => Button::DPadDown,
+        "DPadLeft" => Button::DPadLeft,
+        "DPadRight" => Button::DPadRight,
+        _ => return None,
+    };
+    Some(btn)
+}
+
+fn parse_axis(name: &str) -> Option<Axis> {
+    let axis = match name {
+        "LeftStickX" => Axis::LeftStickX,
+        "LeftStickY" => Axis::LeftStickY,
+        "RightStickX" => Axis::RightStickX,
+        "RightStickY" => Axis::RightStickY,
+        _ => return None,
+    };
+    Some(axis)
+}
+
+/// Parses the entries of a section into `map`, replacing the actions of buttons that are
+/// already in it. `parse_input` parses the names of the buttons or axes,
+/// which are called `input_kind` in error messages.
+fn parse_section<I: std::hash::Hash + Eq, T>(
+    mut map: HashMap<I, T>,
+    section_name: &str,
+    raw: &BTreeMap<String, String>,
+    input_kind: &str,
+    parse_input: impl Fn(&str) -> Option<I>,
+    parse_action: impl Fn(&str) -> Result<T, String>,
+    errors: &mut Vec<String>,
+) -> HashMap<I, T> {
+    for (input_name, action_name) in raw.iter() {
+        let input = match parse_input(input_name) {
+            Some(input) => input,
+            None => {
+                errors.push(format!(
+                    "[{}]: unknown {} \"{}\"",
+                    section_name, input_kind, input_name
+                ));
+                continue;
+            }
+        };
+
+        match parse_action(action_name) {
+            Ok(action) => {
+                map.insert(input, action);
+            }
+            Err(e) => errors.push(format!("[{}]: {}", section_name, e)),
+        }
+    }
+    map
+}
+
+fn parse_analog_action(name: &str) -> Result<AnalogAction, String> {
+    match name {
+        "Rewind" => Ok(AnalogAction::Rewind),
+        "Forward" => Ok(AnalogAction::Forward),
+        _ => Err(format!(
+            "action \"{}\" cannot be controlled by an analog button, only Rewind and Forward can",
+            name
+        )),
+    }
+}
+
+fn parse_axis_action(name: &str) -> Result<AxisAction, String> {
+    match name {
+        "Jog" => Ok(AxisAction::Jog),
+        _ => Err(format!(
+            "action \"{}\" cannot be controlled by a thumbstick, only Jog can",
+            name
+        )),
+    }
+}
+
+/// Builds a button map from the sections of a file. Missing sections are taken from `fallback`.
+/// If `merge` is set, the buttons of a section are added to the ones of `fallback`,
+/// otherwise the section replaces them.
+fn parse_button_map(
+    short_press: &RawSection,
+    long_press: &RawSection,
+    analog: &RawSection,
+    axis: &RawSection,
+    fallback: &ButtonMap,
+    merge: bool,
+    errors: &mut Vec<String>,
+) -> ButtonMap {
+    let parse_action = |name: &str| name.parse::<Action>();
+    fn base<I: Clone, T: Clone>(fallback: &HashMap<I, T>, merge: bool) -> HashMap<I, T> {
+        if merge {
+            fallback.clone()
+        } else {
+            HashMap::new()
+        }
+    }
+
+    ButtonMap {
+        short_press: match short_press {
+            Some(raw) => parse_section(
+                base(&fallback.short_press, merge),
+                "short_press",
+                raw,
+                "button",
+                parse_button,
+                parse_action,
+                errors,
+            ),
+            None => fallback.short_press.clone(),
+        },
+        long_press: match long_press {
+            Some(raw) => parse_section(
+                base(&fallback.long_press, merge),
+                "long_press",
+                raw,
+                "button",
+                parse_button,
+                parse_action,
+                errors,
+            ),
+            None => fallback.long_press.clone(),
+        },
+        analog: match analog {
+            Some(raw) => parse_section(
+                base(&fallback.analog, merge),
+                "analog",
+                raw,
+                "button",
+                parse_button,
+                parse_analog_action,
+                errors,
+            ),
+            None => fallback.analog.clone(),
+        },
+        axis: match axis {
+            Some(raw) => parse_section(
+                base(&fallback.axis, merge),
+                "axis",
+                raw,
+                "axis",
+                parse_axis,
+                parse_axis_action,
+                errors,
+            ),
+            None => fallback.axis.clone(),
+        },
+    }
+}
+
+impl GamepadMapping {
+    /// Loads gamepad.toml from next to the executable or from the user's config directory.
+    /// Falls back to the default Xbox layout if there is no mapping file or it cannot be read.
+    pub(crate) fn load() -> GamepadMapping {
+        match super::find_config_file(GAMEPAD_MAP_FILE_NAME) {
+            Some(path) => GamepadMapping::load_file(&path).unwrap_or_else(|e| {
+                println!("{:?}: {}. Using default gamepad mapping", path, e);
+                GamepadMapping::default()
+            }),
+            None => {
+                println!(
+                    "no {} found, using default gamepad mapping",
+                    GAMEPAD_MAP_FILE_NAME
+                );
+                GamepadMapping::default()
+            }
+        }
+    }
+
+    fn load_file(path: &Path) -> Result<GamepadMapping, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let (mapping, errors) = GamepadMapping::parse(&content)?;
+        for e in errors {
+            println!("{:?}: {}", path, e);
+        }
+        println!("loaded gamepad mapping from {:?}", path);
+
+        Ok(mapping)
+    }
+
+    /// Parses the content of a gamepad mapping file.
+    /// Invalid entries are skipped and reported in the returned list of errors.
+    fn parse(content: &str) -> Result<(GamepadMapping, Vec<String>), String> {
+        let raw: RawGamepadMapFile = toml::from_str(content).map_err(|e| e.to_string())?;
+        let mut errors = Vec::new();
+
+        let default = parse_button_map(
+            &raw.short_press,
+            &raw.long_press,
+            &raw.analog,
+            &raw.axis,
+            &ButtonMap::default_map(),
+            false,
+            &mut errors,
+        );
+
+        let mut profiles = Vec::new();
+        for (i, raw_profile) in raw.profile.iter().enumerate() {
+            if raw_profile.name.is_none() && raw_profile.uuid.is_none() {
+                errors.push(format!(
+                    "profile #{} needs a gamepad name or uuid to match",
+                    i + 1
+                ));
+                continue;
+            }
+
+            let map = parse_button_map(
+                &raw_profile.short_press,
+                &raw_profile.long_press,
+                &raw_profile.analog,
+                &raw_profile.axis,
+                &default,
+                true,
+                &mut errors,
+            );
+            profiles.push(Profile {
+                name: raw_profile.name.clone(),
+                uuid: raw_profile.uuid.clone(),
+                map,
+            });
+        }
+
+        Ok((GamepadMapping { default, profiles }, errors))
+    }
+
+    /// Returns the button map of the first profile matching the gamepad's name or UUID,
+    /// or the default map if there is no such profile.
+    pub(crate) fn button_map(&self, gamepad_name: &str, gamepad_uuid: &[u8; 16]) -> &ButtonMap {
+        let uuid = format_uuid(gamepad_uuid);
+        self.profiles
+            .iter()
+            .find(|profile| profile.matches(gamepad_name, &uuid))
+            .map(|profile| &profile.map)
+            .unwrap_or(&self.default)
+    }
+}
+
+impl Default for GamepadMapping {
+    fn default() -> Self {
+        GamepadMapping {
+            default: ButtonMap::default_map(),
+            profiles: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_shipped_gamepad_map_matches_defaults() {
+        let (mapping, errors) = GamepadMapping::parse(include_str!("../../gamepad.toml")).unwrap();
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(mapping.default, ButtonMap::default_map());
+    }
+
+    #[test]
+    fn test_gamepad_profiles() {
+        let content = r#"
+            [[profile]]
+            name = "Sony PLAYSTATION(R)3 Controller"
+            [profile.short_press]
+            LeftTrigger = "CutLoop_Offense"
+            Select = "Teleport"
+
+            [[profile]]
+            uuid = "03000000-5e04-0000-8e02-000010010000"
+            [profile.long_press]
+            North = "Exit"
+        "#;
+        let (mapping, errors) = GamepadMapping::parse(content).unwrap();
+        assert_eq!(errors.len(), 1);
+
+        let ps3 = mapping.button_map("Sony PLAYSTATION(R)3 Controller", &[0; 16]);
+        assert_eq!(
+            ps3.short_press(Button::LeftTrigger),
+            Some(Action::CutCurrentLoop(vec!["Offense".to_owned()]))
+        );
+        assert_eq!(
+            ps3.short_press(Button::South),
+            Some(Action::TogglePlayPause)
+        );
+        assert_eq!(ps3.short_press(Button::Select), Some(Action::ResetSpeed));
+        assert_eq!(ps3.long_press(Button::North), Some(Action::ConcatClips));
+
+        let uuid = [
+            0x03, 0, 0, 0, 0x5e, 0x04, 0, 0, 0x8e, 0x02, 0, 0, 0x10, 0x01, 0, 0,
+        ];
+        let xbox = mapping.button_map("Xbox 360 Controller", &uuid);
+        assert_eq!(xbox.long_press(Button::North), Some(Action::Exit));
+        assert_eq!(
+            xbox.short_press(Button::South),
+            Some(Action::TogglePlayPause)
+        );
+        assert_eq!(
+            xbox.analog(Button::LeftTrigger2),
+            Some(AnalogAction::Rewind)
+        );
+        assert_eq!(xbox.analog(Button::LeftTrigger), None);
+        assert_eq!(xbox.axis(Axis::RightStickX), Some(AxisAction::Jog));
+
+        let generic = mapping.button_map("Generic USB Joystick", &[0; 16]);
+        assert_eq!(generic, &ButtonMap::default_map());
+    }
+
+    #[test]
+    fn test_profile_overrides_one_button() {
+        let content = r#"
+            [short_press]
+            South = "TogglePlayPause"
+            North = "NextClip"
+
+            [[profile]]
+            name = "Generic USB Joystick"
+            [profile.short_press]
+            North = "Exit"
+        "#;
+        let (mapping, errors) = GamepadMapping::parse(content).unwrap();
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        let generic = mapping.button_map("Generic USB Joystick", &[0; 16]);
+        assert_eq!(generic.short_press(Button::North), Some(Action::Exit));
+        assert_eq!(
+            generic.short_press(Button::South),
+            Some(Action::TogglePlayPause)
+        );
+        assert_eq!(generic.short_press(Button::West), None);
+        assert_eq!(generic.long_press, ButtonMap::default_map().long_press);
+
+        let other = mapping.button_map("Xbox 360 Controller", &[0; 16]);
+        assert_eq!(other.short_press(Button::North), Some(Action::NextClip));
+    }
+}
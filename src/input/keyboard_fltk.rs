@@ -1,53 +1,219 @@
 use fltk::enums::Key;
 use std::collections::BTreeMap;
+use std::path::Path;
 
-use crate::{Action, ClipType};
+use crate::Action;
 
-fn default_keymap() -> BTreeMap<Key, Option<Action>> {
+const KEYMAP_FILE_NAME: &str = "keymap.toml";
+
+fn default_keymap() -> BTreeMap<Key, Action> {
     let mut map = BTreeMap::new();
 
-    map.insert(Key::from_char(' '), Some(Action::TogglePlayPause));
-    map.insert(Key::Left, Some(Action::Rewind(0.7)));
-    map.insert(Key::Right, Some(Action::Forward(0.7)));
-    map.insert(Key::Up, Some(Action::IncreaseSpeed));
-    map.insert(Key::Down, Some(Action::TogglePlayPause));
-    map.insert(Key::from_char('t'), Some(Action::StartLoop));
-    map.insert(Key::from_char('z'), Some(Action::EndLoop));
-    map.insert(Key::from_char('b'), Some(Action::BreakLoop));
+    map.insert(Key::from_char(' '), Action::TogglePlayPause);
+    map.insert(Key::Left, Action::Rewind(0.7));
+    map.insert(Key::Right, Action::Forward(0.7));
+    map.insert(Key::Up, Action::IncreaseSpeed);
+    map.insert(Key::Down, Action::DecreaseSpeed);
+    map.insert(Key::from_char('n'), Action::ResetSpeed);
+    map.insert(Key::from_char('r'), Action::PlayReverse);
+    map.insert(Key::from_char('e'), Action::NextFrame);
+    map.insert(Key::from_char('q'), Action::PreviousFrame);
+    map.insert(Key::from_char('t'), Action::StartLoop);
+    map.insert(Key::from_char('z'), Action::EndLoop);
+    map.insert(Key::from_char('b'), Action::BreakLoop);
+    map.insert(Key::from_char('p'), Action::CycleLoopOptions);
     map.insert(
         Key::from_char('o'),
-        Some(Action::CutCurrentLoop(Some(ClipType::Offense))),
+        Action::CutCurrentLoop(vec!["Offense".to_owned()]),
     );
     map.insert(
         Key::from_char('d'),
-        Some(Action::CutCurrentLoop(Some(ClipType::Defense))),
+        Action::CutCurrentLoop(vec!["Defense".to_owned()]),
+    );
+    map.insert(Key::from_char('c'), Action::CutCurrentLoop(Vec::new()));
+    map.insert(Key::from_char('i'), Action::NextMedia);
+    map.insert(Key::from_char('k'), Action::PreviousMedia);
+    map.insert(Key::from_char('m'), Action::RestartMedia);
+    map.insert(Key::from_char('w'), Action::NextClip);
+    map.insert(Key::from_char('s'), Action::PreviousClip);
+    map.insert(Key::from_char('y'), Action::RestartClip);
+    map.insert(Key::from_char('u'), Action::ConcatClips);
+    map.insert(Key::from_char('0'), Action::PreviousCutmark);
+    map.insert(Key::from_char('1'), Action::NextCutmark);
+    map.insert(Key::from_char('j'), Action::ReviewClips(Vec::new()));
+    map.insert(
+        Key::from_char('f'),
+        Action::ReviewClips(vec!["Offense".to_owned()]),
+    );
+    map.insert(
+        Key::from_char('h'),
+        Action::ReviewClips(vec!["Defense".to_owned()]),
     );
-    map.insert(Key::from_char('c'), Some(Action::CutCurrentLoop(None)));
-    map.insert(Key::from_char('i'), Some(Action::NextMedia));
-    map.insert(Key::from_char('k'), Some(Action::PreviousMedia));
-    map.insert(Key::from_char('m'), Some(Action::RestartMedia));
-    map.insert(Key::from_char('w'), Some(Action::NextClip));
-    map.insert(Key::from_char('s'), Some(Action::PreviousClip));
-    map.insert(Key::from_char('y'), Some(Action::RestartClip));
-    map.insert(Key::from_char('u'), Some(Action::ConcatClips));
-    map.insert(Key::from_char('1'), Some(Action::PreviousCutmark));
-    map.insert(Key::from_char('2'), Some(Action::NextCutmark));
-    map.insert(Key::Escape, Some(Action::Exit));
+    map.insert(Key::from_char('a'), Action::NextAngle);
+    map.insert(Key::from_char('v'), Action::ToggleAngleLayout);
+    map.insert(Key::from_char('g'), Action::ExportPreview);
+    map.insert(Key::from_char('l'), Action::AlignAngles);
+    map.insert(Key::from_char('.'), Action::NudgeAngleForward);
+    map.insert(Key::from_char(','), Action::NudgeAngleBack);
+    map.insert(Key::Escape, Action::Exit);
     map
 }
 
-pub(crate) fn action_from_pressed_key(key: fltk::enums::Key) -> Option<Action> {
-    let keymap = default_keymap();
-    println!("key {} pressed", key.bits() as u32);
-    if let Some(action_option) = keymap.get(&key) {
-        println!(
-            "key {} yields action {:?}",
-            key.bits() as u32,
-            action_option
-        );
+/// Parses key identifiers as used in keymap.toml, e.g. "space", "left", "f5" or "t"
+fn parse_key(name: &str) -> Option<Key> {
+    let key = match name.to_lowercase().as_str() {
+        "space" => Key::from_char(' '),
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "escape" | "esc" => Key::Escape,
+        "enter" | "return" => Key::Enter,
+        "tab" => Key::Tab,
+        "backspace" => Key::BackSpace,
+        "delete" => Key::Delete,
+        "insert" => Key::Insert,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "pause" => Key::Pause,
+        lower => {
+            let mut chars = lower.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if c.is_ascii_graphic() => Key::from_char(c),
+                (Some('f'), Some(_)) => match lower[1..].parse::<i32>() {
+                    // FLTK encodes F1..F12 as FL_F + n
+                    Ok(n) if (1..=12).contains(&n) => Key::from_i32(0xffbd + n),
+                    _ => return None,
+                },
+                _ => return None,
+            }
+        }
+    };
+    Some(key)
+}
+
+/// Parses the content of a keymap file. Every line has the form `ActionName = "key"`, or
+/// `ActionName = ["key1", "key2"]` to bind several keys to one action.
+/// Invalid entries are skipped and reported in the returned list of errors.
+fn parse_keymap(content: &str) -> Result<(BTreeMap<Key, Action>, Vec<String>), String> {
+    let table: toml::value::Table = toml::from_str(content).map_err(|e| e.to_string())?;
+    let mut map = BTreeMap::new();
+    let mut errors = Vec::new();
+
+    for (action_name, value) in table.iter() {
+        let action: Action = match action_name.parse() {
+            Ok(action) => action,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+
+        let key_names = match value {
+            toml::Value::String(key_name) => vec![key_name.as_str()],
+            toml::Value::Array(key_names) => key_names.iter().filter_map(|v| v.as_str()).collect(),
+            _ => {
+                errors.push(format!(
+                    "key for action \"{}\" has to be a string or a list of strings",
+                    action_name
+                ));
+                continue;
+            }
+        };
+
+        for key_name in key_names {
+            match parse_key(key_name) {
+                Some(key) => {
+                    if let Some(previous) = map.insert(key, action.clone()) {
+                        errors.push(format!(
+                            "key \"{}\" is bound to \"{}\" and \"{}\"",
+                            key_name, previous, action_name
+                        ));
+                    }
+                }
+                None => errors.push(format!(
+                    "unknown key \"{}\" for action \"{}\"",
+                    key_name, action_name
+                )),
+            }
+        }
+    }
+
+    Ok((map, errors))
+}
+
+pub(crate) struct Keymap {
+    map: BTreeMap<Key, Action>,
+}
+
+impl Keymap {
+    /// Loads keymap.toml from next to the executable or from the user's config directory.
+    /// Falls back to the default key bindings if there is no keymap file or it cannot be read.
+    pub(crate) fn load() -> Keymap {
+        let map = match super::find_config_file(KEYMAP_FILE_NAME) {
+            Some(path) => Keymap::load_file(&path).unwrap_or_else(|e| {
+                println!("{:?}: {}. Using default key bindings", path, e);
+                default_keymap()
+            }),
+            None => {
+                println!("no {} found, using default key bindings", KEYMAP_FILE_NAME);
+                default_keymap()
+            }
+        };
+
+        Keymap { map }
+    }
+
+    fn load_file(path: &Path) -> Result<BTreeMap<Key, Action>, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let (map, errors) = parse_keymap(&content)?;
+        for e in errors {
+            println!("{:?}: {}", path, e);
+        }
+        println!("loaded key bindings from {:?}", path);
+
+        Ok(map)
+    }
+
+    pub(crate) fn action_from_pressed_key(&self, key: Key) -> Option<Action> {
+        println!("key {} pressed", key.bits() as u32);
+        let action = self.map.get(&key)?;
+        println!("key {} yields action {:?}", key.bits() as u32, action);
 
-        action_option.clone()
-    } else {
-        None
+        Some(action.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_shipped_keymap_matches_defaults() {
+        let (map, errors) = parse_keymap(include_str!("../../keymap.toml")).unwrap();
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(map, default_keymap());
+    }
+
+    #[test]
+    fn test_keymap_errors() {
+        let (map, errors) =
+            parse_keymap("Rewind = \"left\"\nFly = \"f\"\nForward = \"hyperspace\"").unwrap();
+        assert_eq!(map.get(&Key::Left), Some(&Action::Rewind(0.7)));
+        assert_eq!(map.len(), 1);
+        assert_eq!(errors.len(), 2);
+
+        let (map, errors) =
+            parse_keymap("\"Review_Offense+RedZone\" = \"f\"\nReview_ = \"h\"").unwrap();
+        assert_eq!(
+            map.get(&Key::from_char('f')),
+            Some(&Action::ReviewClips(vec![
+                "Offense".to_owned(),
+                "RedZone".to_owned()
+            ]))
+        );
+        assert_eq!(errors.len(), 1);
     }
 }
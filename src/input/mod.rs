@@ -1,2 +1,22 @@
+use std::path::PathBuf;
+
 pub(crate) mod controller;
+mod gamepad_map;
 pub(crate) mod keyboard_fltk;
+
+const CONFIG_DIR_NAME: &str = "VideoAnalysisController";
+
+/// Looks for a configuration file next to the executable first and then in the user's
+/// configuration directory (e.g. `~/.config/VideoAnalysisController` or `%APPDATA%\VideoAnalysisController`).
+pub(crate) fn find_config_file(file_name: &str) -> Option<PathBuf> {
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.to_path_buf()));
+    let user_config_dir = dirs::config_dir().map(|dir| dir.join(CONFIG_DIR_NAME));
+
+    exe_dir
+        .into_iter()
+        .chain(user_config_dir)
+        .map(|dir| dir.join(file_name))
+        .find(|path| path.is_file())
+}
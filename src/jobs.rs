@@ -0,0 +1,379 @@
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    panic::AssertUnwindSafe,
+    path::PathBuf,
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc, Mutex,
+    },
+};
+
+use crate::media::MediaProcessor;
+
+pub(crate) type JobId = u64;
+
+/// One run of ffmpeg
+pub(crate) struct JobStep {
+    /// Arguments for ffmpeg, without the output file
+    pub(crate) args: Vec<OsString>,
+    /// File written by ffmpeg
+    pub(crate) output: PathBuf,
+    /// Expected duration of the output in ms, used to compute the progress
+    pub(crate) duration_ms: i64,
+}
+
+/// A file a job needs only while it runs, e.g. the index file for concatenating clips
+pub(crate) struct TempFile {
+    pub(crate) path: PathBuf,
+    pub(crate) content: String,
+}
+
+/// A list of ffmpeg runs that is executed in the background
+pub(crate) struct Job {
+    /// Shown to the user, e.g. "cut game.mp4 at 0:01:02.300"
+    pub(crate) description: String,
+    /// Executed in order. The output of the last step is the result of the job, the outputs of
+    /// all other steps are intermediate files which are removed when the job is finished.
+    pub(crate) steps: Vec<JobStep>,
+    /// Written when the job starts and removed when it is finished, so that a job which is
+    /// cancelled before it starts leaves nothing behind
+    pub(crate) temp_files: Vec<TempFile>,
+    /// Whether an existing output file is replaced. Otherwise the job fails if it exists.
+    pub(crate) overwrite: bool,
+    /// Called by the worker before the job runs, for preparations which are too slow for the
+    /// GUI thread, e.g. probing the input. The returned job is run instead of this one.
+    pub(crate) prepare: Option<Box<dyn FnOnce() -> Job + Send>>,
+}
+
+impl Job {
+    /// A job consisting of a single ffmpeg run
+    pub(crate) fn single(description: String, step: JobStep) -> Job {
+        Job {
+            description,
+            steps: vec![step],
+            temp_files: Vec::new(),
+            overwrite: false,
+            prepare: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum JobStatus {
+    Queued,
+    /// Progress between 0 and 1
+    Running(f32),
+    Succeeded,
+    Failed(String),
+    Cancelled,
+}
+
+impl JobStatus {
+    pub(crate) fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Succeeded | JobStatus::Failed(_) | JobStatus::Cancelled
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct JobUpdate {
+    pub(crate) id: JobId,
+    pub(crate) description: String,
+    pub(crate) status: JobStatus,
+}
+
+/// Runs jobs one after another in a worker thread with a `MediaProcessor`
+/// and reports their progress and results as `JobUpdate`s.
+pub(crate) struct JobQueue {
+    next_id: JobId,
+    job_tx: Sender<(JobId, Job)>,
+    update_tx: Sender<JobUpdate>,
+    update_rx: Receiver<JobUpdate>,
+    cancelled: Arc<Mutex<HashSet<JobId>>>,
+    unfinished: HashSet<JobId>,
+}
+
+impl JobQueue {
+    pub(crate) fn new(processor: Arc<dyn MediaProcessor>) -> JobQueue {
+        let (job_tx, job_rx) = channel::<(JobId, Job)>();
+        let (update_tx, update_rx) = channel();
+        let cancelled = Arc::new(Mutex::new(HashSet::new()));
+
+        let worker_update_tx = update_tx.clone();
+        let worker_cancelled = cancelled.clone();
+        std::thread::spawn(move || {
+            for (id, mut job) in job_rx {
+                let description = job.description.clone();
+                let send = |status| {
+                    // the receiver only goes away when VAC exits
+                    let _ = worker_update_tx.send(JobUpdate {
+                        id,
+                        description: description.clone(),
+                        status,
+                    });
+                };
+                let is_cancelled = || worker_cancelled.lock().unwrap().contains(&id);
+
+                if is_cancelled() {
+                    send(JobStatus::Cancelled);
+                    continue;
+                }
+                send(JobStatus::Running(0.0));
+                // a panicking job must not take the worker with it, `wait_all` would wait forever
+                let status = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                    if let Some(prepare) = job.prepare.take() {
+                        job = prepare();
+                    }
+                    run_job(processor.as_ref(), &job, &send, &is_cancelled)
+                }))
+                .unwrap_or_else(|panic| {
+                    let message = match panic.downcast_ref::<&str>() {
+                        Some(message) => message.to_string(),
+                        None => panic.downcast_ref::<String>().cloned().unwrap_or_default(),
+                    };
+                    JobStatus::Failed(format!("the job crashed: {}", message))
+                });
+                send(status);
+            }
+        });
+
+        JobQueue {
+            next_id: 0,
+            job_tx,
+            update_tx,
+            update_rx,
+            cancelled,
+            unfinished: HashSet::new(),
+        }
+    }
+
+    pub(crate) fn submit(&mut self, job: Job) -> JobId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.unfinished.insert(id);
+        self.update_tx
+            .send(JobUpdate {
+                id,
+                description: job.description.clone(),
+                status: JobStatus::Queued,
+            })
+            .unwrap();
+        self.job_tx.send((id, job)).unwrap();
+        id
+    }
+
+    /// Cancels a queued job or stops the running one. Finished jobs are left alone.
+    pub(crate) fn cancel(&self, id: JobId) {
+        if self.unfinished.contains(&id) {
+            self.cancelled.lock().unwrap().insert(id);
+        }
+    }
+
+    /// Returns all updates since the last call without blocking
+    pub(crate) fn poll(&mut self) -> Vec<JobUpdate> {
+        let updates: Vec<JobUpdate> = self.update_rx.try_iter().collect();
+        self.forget_finished(&updates);
+        updates
+    }
+
+    /// Blocks until all submitted jobs are finished and returns their updates
+    pub(crate) fn wait_all(&mut self) -> Vec<JobUpdate> {
+        let mut updates = Vec::new();
+        while !self.unfinished.is_empty() {
+            let update = self.update_rx.recv().unwrap();
+            self.forget_finished(std::slice::from_ref(&update));
+            updates.push(update);
+        }
+        updates
+    }
+
+    pub(crate) fn unfinished_count(&self) -> usize {
+        self.unfinished.len()
+    }
+
+    fn forget_finished(&mut self, updates: &[JobUpdate]) {
+        for update in updates.iter().filter(|u| u.status.is_finished()) {
+            self.unfinished.remove(&update.id);
+            self.cancelled.lock().unwrap().remove(&update.id);
+        }
+    }
+}
+
+fn run_job(
+    processor: &dyn MediaProcessor,
+    job: &Job,
+    send: &dyn Fn(JobStatus),
+    is_cancelled: &dyn Fn() -> bool,
+) -> JobStatus {
+    let (last_step, intermediate_steps) = match job.steps.split_last() {
+        Some(steps) => steps,
+        None => return JobStatus::Succeeded,
+    };
+    if !job.overwrite && last_step.output.exists() {
+        return JobStatus::Failed(format!("{:?} already exists", last_step.output));
+    }
+
+    let intermediate_files: Vec<&PathBuf> =
+        intermediate_steps.iter().map(|step| &step.output).collect();
+    for file in intermediate_files.iter() {
+        // leftovers of an earlier run that was interrupted
+        let _ = std::fs::remove_file(file);
+    }
+
+    let temp_file_paths: Vec<&PathBuf> = job.temp_files.iter().map(|file| &file.path).collect();
+    let mut status = JobStatus::Succeeded;
+    for temp_file in job.temp_files.iter() {
+        if let Err(e) = std::fs::write(&temp_file.path, &temp_file.content) {
+            status = JobStatus::Failed(format!("could not write {:?}: {}", temp_file.path, e));
+            break;
+        }
+    }
+
+    let total_duration_ms: i64 = job.steps.iter().map(|step| step.duration_ms).sum();
+    let mut finished_duration_ms = 0;
+    let steps = if status == JobStatus::Succeeded {
+        &job.steps[..]
+    } else {
+        &[]
+    };
+    for step in steps {
+        let send_step_progress = |time_ms: i64| {
+            if total_duration_ms > 0 {
+                let progress = (finished_duration_ms + time_ms.min(step.duration_ms)) as f32
+                    / total_duration_ms as f32;
+                send(JobStatus::Running(progress.clamp(0.0, 1.0)));
+            }
+        };
+
+        status = processor.run_step(step, job.overwrite, &send_step_progress, is_cancelled);
+        if status != JobStatus::Succeeded {
+            let _ = std::fs::remove_file(&last_step.output);
+            break;
+        }
+        finished_duration_ms += step.duration_ms;
+    }
+
+    for file in intermediate_files.into_iter().chain(temp_file_paths) {
+        let _ = std::fs::remove_file(file);
+    }
+    status
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::media::{MediaCall, RecordingProcessor};
+
+    fn step(output: &str) -> JobStep {
+        JobStep {
+            args: Vec::new(),
+            output: PathBuf::from("tests").join("output").join(output),
+            duration_ms: 1000,
+        }
+    }
+
+    #[test]
+    fn test_job_queue() {
+        let mut processor = RecordingProcessor::default();
+        processor.failing_outputs.insert(step("failing.mp4").output);
+        let (gate_tx, gate_rx) = channel();
+        processor
+            .gated_outputs
+            .insert(step("jobs_part.mp4").output, Mutex::new(gate_rx));
+        let processor = Arc::new(processor);
+        let mut jobs = JobQueue::new(processor.clone());
+
+        let job = Job {
+            description: "two steps".to_owned(),
+            steps: vec![step("jobs_part.mp4"), step("jobs_result.mp4")],
+            temp_files: Vec::new(),
+            overwrite: true,
+            prepare: None,
+        };
+        let succeeding = jobs.submit(job);
+        let failing = jobs.submit(Job::single("fails".to_owned(), step("failing.mp4")));
+        let cancelled = jobs.submit(Job::single("cancelled".to_owned(), step("cancelled.mp4")));
+        // the worker is still held in the first job, so the last one cannot have started yet
+        jobs.cancel(cancelled);
+        gate_tx.send(()).unwrap();
+
+        let updates = jobs.wait_all();
+        let final_status = |id| {
+            updates
+                .iter()
+                .rev()
+                .find(|update| update.id == id)
+                .map(|update| update.status.clone())
+        };
+        assert_eq!(final_status(succeeding), Some(JobStatus::Succeeded));
+        assert!(matches!(final_status(failing), Some(JobStatus::Failed(_))));
+        assert_eq!(final_status(cancelled), Some(JobStatus::Cancelled));
+        // the progress of the first of two equally long steps
+        assert!(updates
+            .iter()
+            .any(|update| update.id == succeeding && update.status == JobStatus::Running(0.5)));
+        assert_eq!(jobs.unfinished_count(), 0);
+        assert_eq!(
+            processor.calls(),
+            vec![
+                MediaCall::Run(step("jobs_part.mp4").output),
+                MediaCall::Run(step("jobs_result.mp4").output),
+                MediaCall::Run(step("failing.mp4").output),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prepared_job() {
+        let processor = Arc::new(RecordingProcessor::default());
+        let mut jobs = JobQueue::new(processor.clone());
+        let mut job = Job::single("prepared".to_owned(), step("unprepared.mp4"));
+        let main_thread = std::thread::current().id();
+        job.prepare = Some(Box::new(move || {
+            assert_ne!(std::thread::current().id(), main_thread);
+            Job::single("prepared".to_owned(), step("prepared.mp4"))
+        }));
+        let id = jobs.submit(job);
+
+        let updates = jobs.wait_all();
+        assert_eq!(updates.last().unwrap().id, id);
+        assert_eq!(updates.last().unwrap().status, JobStatus::Succeeded);
+        assert_eq!(
+            processor.calls(),
+            vec![MediaCall::Run(step("prepared.mp4").output)]
+        );
+    }
+
+    #[test]
+    fn test_crashing_job() {
+        let processor = Arc::new(RecordingProcessor::default());
+        let mut jobs = JobQueue::new(processor.clone());
+        let mut crashing = Job::single("crashes".to_owned(), step("crashing.mp4"));
+        crashing.prepare = Some(Box::new(|| panic!("probe failed")));
+        let crashing = jobs.submit(crashing);
+        let next = jobs.submit(Job::single("next".to_owned(), step("jobs_next.mp4")));
+
+        let updates = jobs.wait_all();
+        let final_status = |id| {
+            updates
+                .iter()
+                .rev()
+                .find(|update| update.id == id)
+                .map(|update| update.status.clone())
+        };
+        assert_eq!(
+            final_status(crashing),
+            Some(JobStatus::Failed(
+                "the job crashed: probe failed".to_owned()
+            ))
+        );
+        assert_eq!(final_status(next), Some(JobStatus::Succeeded));
+
+        // cancelling a finished job is ignored
+        jobs.cancel(next);
+        assert!(jobs.cancelled.lock().unwrap().is_empty());
+    }
+}
@@ -1,8 +1,7 @@
 use std::{
     collections::btree_set::BTreeSet,
-    fs::File,
-    io::{self, BufRead},
-    sync::{mpsc::Sender, Arc, Mutex},
+    io,
+    sync::{Arc, Mutex},
 };
 
 use std::string::String;
@@ -14,7 +13,7 @@ use fltk::{
     dialog::FileDialogType,
     prelude::{InputExt, ValuatorExt, WidgetExt, WindowExt},
 };
-use fltk_gui::{FltkGui, GuiActions};
+use fltk_gui::{FltkGui, GuiActions, ReelEdit};
 use vlc::{Instance, MediaPlayer, MediaPlayerVideoEx};
 
 pub mod ffmpeg;
@@ -25,31 +24,52 @@ use std::path::PathBuf;
 mod action_handling;
 use action_handling::ActionHandler;
 
+mod align;
+mod autocutmarks;
+mod cli;
+mod export;
 mod fltk_gui;
+mod jobs;
+mod media;
+mod project;
+mod settings;
 
-use crate::input::{controller::Controller, keyboard_fltk::action_from_pressed_key};
+use crate::input::{controller::Controller, keyboard_fltk::Keymap};
+use crate::settings::Settings;
 
-const CLIP_SUFFIX_OFFENSE: &str = "Off";
-const CLIP_SUFFIX_DEFENSE: &str = "Def";
-
-#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
-pub enum ClipType {
-    Offense,
-    Defense,
-}
+/// Prefix of the names of `CutCurrentLoop` actions that tag the clip, e.g. `CutLoop_Offense+RedZone`
+const CUT_LOOP_TAGGED_PREFIX: &str = "CutLoop_";
+/// Prefix of the names of `ReviewClips` actions that review the clips with tags, e.g. `Review_Offense`
+const REVIEW_TAGGED_PREFIX: &str = "Review_";
+const CUT_LOOP_TAG_SEPARATOR: char = '+';
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum Action {
     TogglePlayPause,
     Rewind(f32),
     Forward(f32),
+    /// Scrubs the video continuously at the given multiple of the normal speed, backwards if it
+    /// is negative, until it is 0
+    Shuttle(f32),
+    /// Plays the video backwards at the current speed, or forwards again if it is played
+    /// backwards already
+    PlayReverse,
     IncreaseSpeed,
     DecreaseSpeed,
+    /// Plays at normal speed again
+    ResetSpeed,
+    /// Pauses and shows the next frame
+    NextFrame,
+    /// Pauses and shows the previous frame
+    PreviousFrame,
     StartLoop,
     EndLoop,
     BreakLoop,
+    /// Chooses the next loop options of the project, e.g. how often a loop is played
+    CycleLoopOptions,
     //CheckLoopEnd(f32),
-    CutCurrentLoop(Option<ClipType>),
+    /// Cuts the current loop into a clip marked with the project's tags of the given names
+    CutCurrentLoop(Vec<String>),
     NextMedia,
     PreviousMedia,
     RestartMedia,
@@ -59,26 +79,52 @@ pub enum Action {
     ConcatClips,
     PreviousCutmark,
     NextCutmark,
+    /// Plays the clips of the project with all of the given tags one after the other, or stops
+    /// reviewing them
+    ReviewClips(Vec<String>),
+    /// Shows and plays the audio of the next angle of the current video
+    NextAngle,
+    /// Switches between showing only the active angle and all angles side by side
+    ToggleAngleLayout,
+    /// Writes an animation and a contact sheet of the current loop, or of the current clip
+    /// if no loop is set
+    ExportPreview,
+    /// Computes the offsets of the other angles of the current video from their audio
+    AlignAngles,
+    /// Shows a later moment in the active angle, to correct its offset by hand
+    NudgeAngleForward,
+    /// Shows an earlier moment in the active angle, to correct its offset by hand
+    NudgeAngleBack,
     Stop,
     Exit,
 }
 
-impl From<Action> for &str {
-    fn from(action: Action) -> Self {
-        match action {
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
             Action::TogglePlayPause => "TogglePlayPause",
             Action::Rewind(_) => "Rewind",
             Action::Forward(_) => "Forward",
+            Action::Shuttle(_) => "Shuttle",
+            Action::PlayReverse => "PlayReverse",
             Action::IncreaseSpeed => "IncreaseSpeed",
             Action::DecreaseSpeed => "DecreaseSpeed",
+            Action::ResetSpeed => "ResetSpeed",
+            Action::NextFrame => "NextFrame",
+            Action::PreviousFrame => "PreviousFrame",
             Action::StartLoop => "StartLoop",
             Action::EndLoop => "EndLoop",
             Action::BreakLoop => "BreakLoop",
-            Action::CutCurrentLoop(od) => match od {
-                Some(ClipType::Offense) => "CutLoop_Offense",
-                Some(ClipType::Defense) => "CutLoop_Defense",
-                None => "CutLoop",
-            },
+            Action::CycleLoopOptions => "CycleLoopOptions",
+            Action::CutCurrentLoop(tags) if tags.is_empty() => "CutLoop",
+            Action::CutCurrentLoop(tags) => {
+                return write!(
+                    f,
+                    "{}{}",
+                    CUT_LOOP_TAGGED_PREFIX,
+                    tags.join(&CUT_LOOP_TAG_SEPARATOR.to_string())
+                )
+            }
             Action::NextMedia => "NextMedia",
             Action::PreviousMedia => "PreviousMedia",
             Action::RestartMedia => "RestartMedia",
@@ -87,15 +133,121 @@ impl From<Action> for &str {
             Action::RestartClip => "RestartClip",
             Action::ConcatClips => "ConcatClips",
             Action::PreviousCutmark => "PreviousCutmark",
-            Action::NextCutmark => "NextCutmarks",
+            Action::NextCutmark => "NextCutmark",
+            Action::ReviewClips(tags) if tags.is_empty() => "Review",
+            Action::ReviewClips(tags) => {
+                return write!(
+                    f,
+                    "{}{}",
+                    REVIEW_TAGGED_PREFIX,
+                    tags.join(&CUT_LOOP_TAG_SEPARATOR.to_string())
+                )
+            }
+            Action::NextAngle => "NextAngle",
+            Action::ToggleAngleLayout => "ToggleAngleLayout",
+            Action::ExportPreview => "ExportPreview",
+            Action::AlignAngles => "AlignAngles",
+            Action::NudgeAngleForward => "NudgeAngleForward",
+            Action::NudgeAngleBack => "NudgeAngleBack",
             Action::Stop => "Stop",
             Action::Exit => "Exit",
+        };
+        f.write_str(name)
+    }
+}
+
+impl Action {
+    /// All actions that can be bound to a key or a button. Actions with a parameter carry the
+    /// parameter that is used when the action is triggered by a key press.
+    fn bindable() -> Vec<Action> {
+        vec![
+            Action::TogglePlayPause,
+            Action::Rewind(0.7),
+            Action::Forward(0.7),
+            Action::PlayReverse,
+            Action::IncreaseSpeed,
+            Action::DecreaseSpeed,
+            Action::ResetSpeed,
+            Action::NextFrame,
+            Action::PreviousFrame,
+            Action::StartLoop,
+            Action::EndLoop,
+            Action::BreakLoop,
+            Action::CycleLoopOptions,
+            Action::CutCurrentLoop(Vec::new()),
+            Action::NextMedia,
+            Action::PreviousMedia,
+            Action::RestartMedia,
+            Action::NextClip,
+            Action::PreviousClip,
+            Action::RestartClip,
+            Action::ConcatClips,
+            Action::PreviousCutmark,
+            Action::NextCutmark,
+            Action::ReviewClips(Vec::new()),
+            Action::NextAngle,
+            Action::ToggleAngleLayout,
+            Action::ExportPreview,
+            Action::AlignAngles,
+            Action::NudgeAngleForward,
+            Action::NudgeAngleBack,
+            Action::Stop,
+            Action::Exit,
+        ]
+    }
+}
+
+impl std::str::FromStr for Action {
+    type Err = String;
+
+    /// Parses the action names produced by `impl Display for Action`.
+    /// The tags of `CutLoop_<Tag>+<Tag>..` and `Review_<Tag>+<Tag>..` are checked against the
+    /// project when the action is handled.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let parse_tags = |tags: &str| {
+            let tags: Vec<String> = tags
+                .split(CUT_LOOP_TAG_SEPARATOR)
+                .map(|tag| tag.trim().to_owned())
+                .collect();
+            if tags.iter().any(|tag| tag.is_empty()) {
+                return Err(format!("empty tag name in action \"{}\"", name));
+            }
+            Ok(tags)
+        };
+        if let Some(tags) = name.strip_prefix(CUT_LOOP_TAGGED_PREFIX) {
+            return Ok(Action::CutCurrentLoop(parse_tags(tags)?));
+        }
+        if let Some(tags) = name.strip_prefix(REVIEW_TAGGED_PREFIX) {
+            return Ok(Action::ReviewClips(parse_tags(tags)?));
         }
+
+        Action::bindable()
+            .into_iter()
+            .find(|action| action.to_string() == name)
+            .ok_or_else(|| format!("unknown action \"{}\"", name))
     }
 }
 
 const VIDEO_EXTENSIONS: &[&str] = &["MOV", "MPEG", "MP4"];
 
+/// Returns the videos in `dir`, sorted by path
+fn list_media(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut media_paths: Vec<PathBuf> = dir
+        .read_dir()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(
+            |path| match path.extension().and_then(|extension| extension.to_str()) {
+                Some(extension) => VIDEO_EXTENSIONS.contains(&extension.to_uppercase().as_str()),
+                None => false,
+            },
+        )
+        .collect();
+
+    media_paths.sort();
+    Ok(media_paths)
+}
+
 /*fn check_loop_end(tx_orig: &std::sync::mpsc::Sender<Action>,
                   mdp: MediaPlayer,
                   loop_start: i64,
@@ -161,19 +313,55 @@ fn load_media(
 }
  */
 
+/// Lets VLC draw the video of `mdp` into an FLTK window
+fn show_in_window(mdp: &MediaPlayer, win: &fltk::window::DoubleWindow) {
+    let handle: fltk::window::RawHandle = win.raw_handle();
+
+    #[cfg(target_os = "windows")]
+    mdp.set_hwnd(handle);
+
+    #[cfg(target_os = "linux")]
+    mdp.set_xwindow(handle.try_into().unwrap()); // TODO unchecked u64 -> u32 conversion
+
+    // Disable event handling on vlc's side
+    // Do it thru fltk
+    mdp.set_key_input(false);
+    mdp.set_mouse_input(false);
+}
+
 fn main() {
-    //startVLC(None, None);
-    run_with_fltk();
+    let args: Vec<String> = std::env::args().collect();
+    std::process::exit(cli::run(&args));
 }
 
-fn run_with_fltk() {
+/// Opens the player window. Without `project_dir`, the user chooses the project directory first.
+fn run_with_fltk(project_dir: Option<PathBuf>) {
     let fltk_gui = FltkGui::new();
-    start_vlc(Some(fltk_gui))
+    start_vlc(Some(fltk_gui), project_dir)
 }
 
-fn start_vlc(mut fltk_gui: Option<FltkGui>) {
-    let args: Vec<String> = std::env::args().collect();
-    let mut controller = Controller::new();
+fn start_vlc(mut fltk_gui: Option<FltkGui>, mut project_dir: Option<PathBuf>) {
+    let settings = Settings::load();
+    let mut controller = Controller::new(settings.gamepad.clone());
+    let keymap = Keymap::load();
+    let (processor, tool_checks) = ffmpeg::FfmpegProcessor::discover(&settings.tools);
+    for check in tool_checks.iter() {
+        println!("{}", check);
+    }
+    if let Some(gui) = &mut fltk_gui {
+        gui.show_tool_status(&tool_checks);
+        let problems: Vec<String> = tool_checks
+            .iter()
+            .filter(|check| check.problem.is_some())
+            .map(|check| check.to_string())
+            .collect();
+        if !problems.is_empty() {
+            fltk::dialog::alert_default(&format!(
+                "Clips cannot be cut or concatenated:\n{}",
+                problems.join("\n")
+            ));
+        }
+    }
 
     let instance = Instance::new().unwrap();
     /*let vlc_args: Vec<String> = vec![
@@ -191,56 +379,95 @@ fn start_vlc(mut fltk_gui: Option<FltkGui>) {
     }*/
 
     let mdp = MediaPlayer::new(&instance).unwrap();
+    let mut angle_mdps = Vec::new();
 
     if let Some(gui) = &fltk_gui {
-        let handle: fltk::window::RawHandle = gui.vlc_win.raw_handle();
-
-        #[cfg(target_os = "windows")]
-        mdp.set_hwnd(handle);
-
-        #[cfg(target_os = "linux")]
-        mdp.set_xwindow(handle.try_into().unwrap()); // TODO unchecked u64 -> u32 conversion
-
-        // Disable event handling on vlc's side
-        // Do it thru fltk
-        mdp.set_key_input(false);
-        mdp.set_mouse_input(false);
+        show_in_window(&mdp, &gui.vlc_win);
+        for angle_win in gui.angle_wins.iter() {
+            let angle_mdp = MediaPlayer::new(&instance).unwrap();
+            show_in_window(&angle_mdp, angle_win);
+            angle_mdps.push(angle_mdp);
+        }
     } else if !mdp.get_fullscreen() {
+        // without windows for them, other angles are only cut
         mdp.toggle_fullscreen();
     }
 
     let mut acm_exe_path: Option<PathBuf> = None;
 
-    let (tx_cutmarks_ready, rx_cutmarks_ready) = channel::<Arc<Mutex<Box<Cutmarks>>>>();
-
-    let project_dir = if let Some(s) = args.get(1) {
-        PathBuf::from(s.clone())
-    } else {
-        loop {
-            if fltk::app::wait() {
-                if let Some(GuiActions::SetProjectDirectory(dir)) =
-                    fltk_gui.as_mut().unwrap().gui_actions_receiver.recv()
-                {
-                    break PathBuf::from(dir);
+    let (tx_cutmarks_ready, rx_cutmarks_ready) = channel::<(PathBuf, Arc<Mutex<Box<Cutmarks>>>)>();
+
+    let (project, media_paths) = loop {
+        let dir = match (project_dir.take(), &mut fltk_gui) {
+            (Some(dir), _) => dir,
+            (None, Some(gui)) => loop {
+                if fltk::app::wait() {
+                    if let Some(GuiActions::SetProjectDirectory(dir)) =
+                        gui.gui_actions_receiver.recv()
+                    {
+                        break PathBuf::from(dir);
+                    }
+                }
+            },
+            (None, None) => return,
+        };
+        match ActionHandler::load_project(&dir) {
+            Ok(loaded) => break loaded,
+            Err(e) => {
+                println!("cannot open project {:?}: {}", dir, e);
+                if fltk_gui.is_some() {
+                    fltk::dialog::alert_default(&format!(
+                        "Cannot open project {:?}: {}\nPlease choose another directory.",
+                        dir, e
+                    ));
                 }
             }
         }
     };
 
-    let mut action_handler = ActionHandler::new(&instance, mdp, project_dir).unwrap();
+    let mut action_handler = ActionHandler::new(
+        &instance,
+        mdp,
+        angle_mdps,
+        project,
+        media_paths,
+        std::sync::Arc::new(processor),
+        settings.playback,
+    );
+    let mut angle_view = action_handler.angle_view();
+    if let Some(gui) = &mut fltk_gui {
+        gui.show_cut_mode(action_handler.project().cut_settings().mode);
+        gui.show_angles(&angle_view);
+    }
 
     loop {
         let event_happened = fltk::app::wait_for(0.01).unwrap();
 
         action_handler.check_loop_end();
+        action_handler.sync_angles();
+        action_handler.shuttle();
+        action_handler.check_review();
+        let job_updates = action_handler.poll_jobs();
 
-        if let Ok(cutmark_mutex) = rx_cutmarks_ready.try_recv() {
+        if let Ok((media_path, cutmark_mutex)) = rx_cutmarks_ready.try_recv() {
             let guard = cutmark_mutex.lock().unwrap();
             let cutmarks = guard.clone(); //TODO: does this clone the BTreeSet? If yes, rather use cutmark_mutex.into_inner()?
-            action_handler.set_cutmarks(cutmarks)
+            action_handler.set_cutmarks(&media_path, cutmarks)
         }
 
         if let Some(gui) = &mut fltk_gui {
+            for update in job_updates.iter() {
+                gui.job_list.update(update);
+            }
+
+            if action_handler.angle_view() != angle_view {
+                angle_view = action_handler.angle_view();
+                gui.show_angles(&angle_view);
+            }
+
+            gui.show_rate(action_handler.get_rate());
+            gui.show_review(action_handler.review_position());
+
             if !gui.slider.has_focus() {
                 gui.slider
                     .set_value(action_handler.get_media_relative_position() as f64);
@@ -250,9 +477,48 @@ fn start_vlc(mut fltk_gui: Option<FltkGui>) {
                 if let Some(gui_action) = gui.gui_actions_receiver.recv() {
                     match gui_action {
                         GuiActions::SetProjectDirectory(dir) => {
-                            action_handler
-                                .set_project_directory(PathBuf::from(dir))
-                                .unwrap();
+                            match action_handler.set_project_directory(PathBuf::from(&dir)) {
+                                Ok(()) => {
+                                    gui.show_cut_mode(action_handler.project().cut_settings().mode);
+                                    // the reels of the previous project
+                                    gui.reel_editor.hide();
+                                }
+                                Err(e) => {
+                                    println!("cannot open project {:?}: {}", dir, e);
+                                    fltk::dialog::alert_default(&format!(
+                                        "Cannot open project {:?}: {}",
+                                        dir, e
+                                    ));
+                                }
+                            }
+                        }
+
+                        GuiActions::SetCutMode(mode) => {
+                            action_handler.set_cut_mode(mode);
+                        }
+
+                        GuiActions::EditReels => {
+                            gui.reel_editor.show(action_handler.project());
+                        }
+
+                        GuiActions::Reel(ReelEdit::Render) => {
+                            if let Some(reel) = gui.reel_editor.selected_reel().cloned() {
+                                if let Err(e) = action_handler.render_reel(&reel) {
+                                    fltk::dialog::alert_default(&e);
+                                }
+                            }
+                        }
+
+                        GuiActions::Reel(edit) => {
+                            if gui.reel_editor.apply(edit, action_handler.project()) {
+                                action_handler.set_reels(gui.reel_editor.reels().to_vec());
+                            }
+                        }
+
+                        GuiActions::CancelJob => {
+                            if let Some(id) = gui.job_list.selected_job() {
+                                action_handler.cancel_job(id);
+                            }
                         }
 
                         GuiActions::SetMediaPosition(pos) => {
@@ -260,7 +526,7 @@ fn start_vlc(mut fltk_gui: Option<FltkGui>) {
                         }
 
                         GuiActions::KeyEvent(key) => {
-                            if let Some(action) = action_from_pressed_key(key) {
+                            if let Some(action) = keymap.action_from_pressed_key(key) {
                                 if let Err(e) = action_handler.handle(action) {
                                     println!("exiting because of: {}", e);
                                     break;
@@ -279,7 +545,7 @@ fn start_vlc(mut fltk_gui: Option<FltkGui>) {
                             Some(ref path) => {
                                 let threshold_near: u64 =
                                     gui.calib_near_input.value().parse().unwrap();
-                                autocutmarks_calibrate_near(
+                                autocutmarks::calibrate_near(
                                     path,
                                     action_handler.get_current_media_path().unwrap(),
                                     action_handler.get_current_frame(),
@@ -296,7 +562,7 @@ fn start_vlc(mut fltk_gui: Option<FltkGui>) {
                             Some(ref path) => {
                                 let threshold_far: u64 =
                                     gui.calib_far_input.value().parse().unwrap();
-                                autocutmarks_calibrate_far(
+                                autocutmarks::calibrate_far(
                                     path,
                                     action_handler.get_current_media_path().unwrap(),
                                     action_handler.get_current_frame(),
@@ -326,7 +592,7 @@ fn start_vlc(mut fltk_gui: Option<FltkGui>) {
                                     } else {
                                         Some(end_frame_value.parse().unwrap()) //TODO error handling
                                     };
-                                    analyze_autocutmarks(
+                                    autocutmarks::analyze(
                                         path,
                                         action_handler.get_current_media_path().unwrap(),
                                         start_frame,
@@ -351,7 +617,7 @@ fn start_vlc(mut fltk_gui: Option<FltkGui>) {
                                     Some(sensitivity_value.parse().unwrap())
                                     //TODO error handling
                                 };
-                                analyze_autocutmarks_cached(
+                                autocutmarks::analyze_cached(
                                     path,
                                     action_handler.get_current_media_path().unwrap(),
                                     action_handler.get_fps(),
@@ -365,6 +631,43 @@ fn start_vlc(mut fltk_gui: Option<FltkGui>) {
                             }
                         },
 
+                        GuiActions::ExportClips(path) => {
+                            match export::export_clips(action_handler.project(), Path::new(&path)) {
+                                Ok(count) => {
+                                    println!("exported {} clips to {}", count, path)
+                                }
+                                Err(e) => fltk::dialog::alert_default(&e),
+                            }
+                        }
+
+                        GuiActions::EditAnnotation => {
+                            let media_path = action_handler.get_current_media_path().cloned();
+                            match (media_path, action_handler.get_current_clip()) {
+                                (Some(media_path), Some((start, clip))) => gui
+                                    .annotation_form
+                                    .show(media_path, start, &clip.annotation),
+                                _ => println!("there is no clip at the current position"),
+                            }
+                        }
+
+                        GuiActions::SaveAnnotation => {
+                            let annotation = gui.annotation_form.annotation();
+                            match (gui.annotation_form.clip.clone(), annotation) {
+                                (Some(_), Err(e)) => gui.annotation_form.show_error(&e),
+                                (Some((media_path, start)), Ok(annotation)) => {
+                                    if let Err(e) = action_handler.set_clip_annotation(
+                                        &media_path,
+                                        start,
+                                        annotation,
+                                    ) {
+                                        println!("could not save annotation: {}", e);
+                                    }
+                                    gui.annotation_form.hide();
+                                }
+                                (None, _) => gui.annotation_form.hide(),
+                            }
+                        }
+
                         GuiActions::SetStartFrame => {
                             let start_frame = action_handler.get_current_frame();
                             dbg!("set start frame to {}", start_frame);
@@ -390,112 +693,9 @@ fn start_vlc(mut fltk_gui: Option<FltkGui>) {
         }
     }
 
+    action_handler.wait_for_jobs();
     println!("exiting");
     std::process::exit(0);
 }
 
 type Cutmarks = BTreeSet<i64>;
-
-fn analyze_autocutmarks(
-    acm_exe_path: &Path,
-    videofile: &Path,
-    start_frame: Option<i64>,
-    end_frame: Option<i64>,
-    fps: f32,
-    tx: Sender<Arc<Mutex<Box<Cutmarks>>>>,
-) {
-    let acm_exe_path = acm_exe_path.to_path_buf();
-    let videofile = videofile.to_path_buf();
-    std::thread::spawn(move || {
-        let mut cmd = std::process::Command::new(acm_exe_path);
-
-        if let Some(start) = start_frame {
-            cmd.arg(format!("-s {}", start));
-        }
-
-        if let Some(end) = end_frame {
-            cmd.arg(format!("-e {}", end));
-        }
-        let status = cmd.arg(videofile).arg("snaps.txt").status().unwrap();
-
-        if status.success() {
-            let cutmarks = read_cutmarks_file(fps);
-            tx.send(Arc::new(Mutex::new(cutmarks))).unwrap();
-        }
-    });
-}
-
-fn analyze_autocutmarks_cached(
-    acm_exe_path: &Path,
-    videofile: &Path,
-    fps: f32,
-    sensitivity: Option<f32>,
-    tx: Sender<Arc<Mutex<Box<Cutmarks>>>>,
-) {
-    let acm_exe_path = acm_exe_path.to_path_buf();
-    let videofile = videofile.to_path_buf();
-    std::thread::spawn(move || {
-        let mut cmd = std::process::Command::new(acm_exe_path);
-        cmd.arg("--mode=use-cached");
-
-        if let Some(sensitivity) = sensitivity {
-            cmd.arg(format!("--sensitivity={}", sensitivity));
-        };
-
-        let status = cmd.arg(videofile).arg("snaps.txt").status().unwrap();
-
-        if status.success() {
-            let cutmarks = read_cutmarks_file(fps);
-            tx.send(Arc::new(Mutex::new(cutmarks))).unwrap();
-        }
-    });
-}
-
-fn autocutmarks_calibrate_near(
-    acm_exe_path: &Path,
-    videofile: &Path,
-    start_frame: i64,
-    threshold: u64,
-) {
-    let status = std::process::Command::new(acm_exe_path)
-        .arg("--mode=calibrate-near")
-        .arg(format!("--thresholdNear={}", threshold))
-        .arg(format!("-s {}", start_frame))
-        .arg(videofile)
-        .arg("snaps.txt")
-        .status()
-        .unwrap();
-
-    assert!(status.success());
-}
-
-fn autocutmarks_calibrate_far(
-    acm_exe_path: &Path,
-    videofile: &Path,
-    start_frame: i64,
-    threshold: u64,
-) {
-    let status = std::process::Command::new(acm_exe_path)
-        .arg("--mode=calibrate-far")
-        .arg(format!("--thresholdFar={}", threshold))
-        .arg(format!("-s {}", start_frame))
-        .arg(videofile)
-        .arg("snaps.txt")
-        .status()
-        .unwrap();
-
-    assert!(status.success());
-}
-
-fn read_cutmarks_file(fps: f32) -> Box<Cutmarks> {
-    let cutmarks_file = File::open("snaps.txt").unwrap();
-    let lines = io::BufReader::new(cutmarks_file).lines();
-    let mut cutmarks = Box::new(Cutmarks::new());
-    for line in lines.flatten() {
-        let cutmark: u64 = line.parse().unwrap();
-        let time = (cutmark as f32 / fps * 1000.0) as i64;
-        cutmarks.insert(time);
-    }
-
-    cutmarks
-}
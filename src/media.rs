@@ -0,0 +1,354 @@
+//! The operations VAC needs from a media tool. `ffmpeg::FfmpegProcessor` runs the ffmpeg and
+//! ffprobe executables, tests use `RecordingProcessor`, which neither spawns processes nor
+//! writes files.
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    ffmpeg::{
+        CondensedVideo, CutSettings, Decorations, EncoderPreset, MediaFormat, PreviewSettings,
+    },
+    jobs::{Job, JobStatus, JobStep},
+};
+
+pub(crate) trait MediaProcessor: Send + Sync {
+    /// Format and duration of a video
+    fn probe(&self, input_path: &Path) -> Result<MediaFormat, String>;
+
+    /// Creates the job which cuts `start_ms` to `end_ms` out of `input_path` into `output_path`
+    fn cut(
+        &self,
+        description: String,
+        input_path: &Path,
+        start_ms: i64,
+        end_ms: i64,
+        output_path: &Path,
+        settings: &CutSettings,
+    ) -> Job;
+
+    /// Creates the job which concatenates the probed clips in the given order into
+    /// `<output_dir_path>/<output_file_stem>.<extension>`
+    fn concat(
+        &self,
+        description: String,
+        clips: &[(PathBuf, MediaFormat)],
+        output_dir_path: &Path,
+        output_file_stem: &str,
+        encoder: &EncoderPreset,
+        decorations: Option<Decorations>,
+    ) -> Result<(Job, CondensedVideo), String>;
+
+    /// Creates the job which saves the frame at `time_ms` of `input_path` as an image
+    fn thumbnail(
+        &self,
+        description: String,
+        input_path: &Path,
+        time_ms: i64,
+        output_path: &Path,
+    ) -> Job;
+
+    /// Creates the job which turns `start_ms` to `end_ms` of `input_path` into an animated GIF or
+    /// WebP, as chosen in `settings`
+    fn animation(
+        &self,
+        description: String,
+        input_path: &Path,
+        start_ms: i64,
+        end_ms: i64,
+        settings: &PreviewSettings,
+        output_path: &Path,
+    ) -> Job;
+
+    /// Creates the job which writes an image with a grid of frames taken evenly across
+    /// `start_ms` to `end_ms` of `input_path`
+    fn contact_sheet(
+        &self,
+        description: String,
+        input_path: &Path,
+        start_ms: i64,
+        end_ms: i64,
+        settings: &PreviewSettings,
+        output_path: &Path,
+    ) -> Job;
+
+    /// Creates the job which writes the first `duration_ms` of the audio of `input_path` as raw
+    /// mono PCM (signed 16 bit little endian) with `sample_rate` samples per second
+    fn extract_audio(
+        &self,
+        description: String,
+        input_path: &Path,
+        duration_ms: i64,
+        sample_rate: u32,
+        output_path: &Path,
+    ) -> Job;
+
+    /// Runs one step of a job. `send_progress` is called with the time written so far in ms.
+    fn run_step(
+        &self,
+        step: &JobStep,
+        overwrite: bool,
+        send_progress: &dyn Fn(i64),
+        is_cancelled: &dyn Fn() -> bool,
+    ) -> JobStatus;
+}
+
+#[cfg(test)]
+pub(crate) use self::recording::{MediaCall, RecordingProcessor};
+
+#[cfg(test)]
+mod recording {
+    use std::{
+        collections::{HashMap, HashSet},
+        path::{Path, PathBuf},
+        sync::{mpsc::Receiver, Mutex},
+    };
+
+    use super::MediaProcessor;
+    use crate::{
+        ffmpeg::{
+            ConcatStrategy, CondensedVideo, CutMode, CutSettings, Decorations, EncoderPreset,
+            MediaFormat, PreviewSettings,
+        },
+        jobs::{Job, JobStatus, JobStep},
+    };
+
+    /// An operation requested from a `RecordingProcessor`
+    #[derive(Debug, Clone, PartialEq)]
+    pub(crate) enum MediaCall {
+        Probe(PathBuf),
+        Cut {
+            input: PathBuf,
+            start_ms: i64,
+            end_ms: i64,
+            output: PathBuf,
+            mode: CutMode,
+        },
+        Concat {
+            clips: Vec<PathBuf>,
+            output: PathBuf,
+        },
+        Thumbnail {
+            input: PathBuf,
+            time_ms: i64,
+            output: PathBuf,
+        },
+        /// An animation or a contact sheet
+        Preview {
+            input: PathBuf,
+            start_ms: i64,
+            end_ms: i64,
+            output: PathBuf,
+        },
+        ExtractAudio {
+            input: PathBuf,
+            output: PathBuf,
+        },
+        /// A step of a job was run
+        Run(PathBuf),
+    }
+
+    /// Records the requested operations instead of executing them
+    #[derive(Default)]
+    pub(crate) struct RecordingProcessor {
+        calls: Mutex<Vec<MediaCall>>,
+        /// Returned by `probe`. Other files cannot be probed.
+        pub(crate) formats: HashMap<PathBuf, MediaFormat>,
+        /// Steps writing these files fail
+        pub(crate) failing_outputs: HashSet<PathBuf>,
+        /// Steps writing these files wait until the test sends something or drops the sender
+        pub(crate) gated_outputs: HashMap<PathBuf, Mutex<Receiver<()>>>,
+    }
+
+    impl RecordingProcessor {
+        pub(crate) fn calls(&self) -> Vec<MediaCall> {
+            self.calls.lock().unwrap().clone()
+        }
+
+        fn record(&self, call: MediaCall) {
+            self.calls.lock().unwrap().push(call);
+        }
+
+        fn preview(
+            &self,
+            description: String,
+            input_path: &Path,
+            start_ms: i64,
+            end_ms: i64,
+            output_path: &Path,
+        ) -> Job {
+            self.record(MediaCall::Preview {
+                input: input_path.to_path_buf(),
+                start_ms,
+                end_ms,
+                output: output_path.to_path_buf(),
+            });
+            Job::single(
+                description,
+                JobStep {
+                    args: Vec::new(),
+                    output: output_path.to_path_buf(),
+                    duration_ms: end_ms - start_ms,
+                },
+            )
+        }
+    }
+
+    impl MediaProcessor for RecordingProcessor {
+        fn probe(&self, input_path: &Path) -> Result<MediaFormat, String> {
+            self.record(MediaCall::Probe(input_path.to_path_buf()));
+            self.formats
+                .get(input_path)
+                .cloned()
+                .ok_or_else(|| format!("{:?} cannot be probed", input_path))
+        }
+
+        fn cut(
+            &self,
+            description: String,
+            input_path: &Path,
+            start_ms: i64,
+            end_ms: i64,
+            output_path: &Path,
+            settings: &CutSettings,
+        ) -> Job {
+            self.record(MediaCall::Cut {
+                input: input_path.to_path_buf(),
+                start_ms,
+                end_ms,
+                output: output_path.to_path_buf(),
+                mode: settings.mode,
+            });
+            Job::single(
+                description,
+                JobStep {
+                    args: Vec::new(),
+                    output: output_path.to_path_buf(),
+                    duration_ms: end_ms - start_ms,
+                },
+            )
+        }
+
+        fn concat(
+            &self,
+            description: String,
+            clips: &[(PathBuf, MediaFormat)],
+            output_dir_path: &Path,
+            output_file_stem: &str,
+            _encoder: &EncoderPreset,
+            _decorations: Option<Decorations>,
+        ) -> Result<(Job, CondensedVideo), String> {
+            let output = output_dir_path.join(format!("{}.mp4", output_file_stem));
+            self.record(MediaCall::Concat {
+                clips: clips.iter().map(|(path, _)| path.clone()).collect(),
+                output: output.clone(),
+            });
+            let job = Job::single(
+                description,
+                JobStep {
+                    args: Vec::new(),
+                    output: output.clone(),
+                    duration_ms: clips.iter().map(|(_, format)| format.duration_ms).sum(),
+                },
+            );
+            let condensed_video = CondensedVideo {
+                path: output,
+                clip_count: clips.len(),
+                strategy: ConcatStrategy::StreamCopy {
+                    extension: "mp4".to_owned(),
+                },
+            };
+            Ok((job, condensed_video))
+        }
+
+        fn thumbnail(
+            &self,
+            description: String,
+            input_path: &Path,
+            time_ms: i64,
+            output_path: &Path,
+        ) -> Job {
+            self.record(MediaCall::Thumbnail {
+                input: input_path.to_path_buf(),
+                time_ms,
+                output: output_path.to_path_buf(),
+            });
+            Job::single(
+                description,
+                JobStep {
+                    args: Vec::new(),
+                    output: output_path.to_path_buf(),
+                    duration_ms: 0,
+                },
+            )
+        }
+
+        fn animation(
+            &self,
+            description: String,
+            input_path: &Path,
+            start_ms: i64,
+            end_ms: i64,
+            _settings: &PreviewSettings,
+            output_path: &Path,
+        ) -> Job {
+            self.preview(description, input_path, start_ms, end_ms, output_path)
+        }
+
+        fn contact_sheet(
+            &self,
+            description: String,
+            input_path: &Path,
+            start_ms: i64,
+            end_ms: i64,
+            _settings: &PreviewSettings,
+            output_path: &Path,
+        ) -> Job {
+            self.preview(description, input_path, start_ms, end_ms, output_path)
+        }
+
+        fn extract_audio(
+            &self,
+            description: String,
+            input_path: &Path,
+            duration_ms: i64,
+            _sample_rate: u32,
+            output_path: &Path,
+        ) -> Job {
+            self.record(MediaCall::ExtractAudio {
+                input: input_path.to_path_buf(),
+                output: output_path.to_path_buf(),
+            });
+            let mut job = Job::single(
+                description,
+                JobStep {
+                    args: Vec::new(),
+                    output: output_path.to_path_buf(),
+                    duration_ms,
+                },
+            );
+            job.overwrite = true;
+            job
+        }
+
+        fn run_step(
+            &self,
+            step: &JobStep,
+            _overwrite: bool,
+            send_progress: &dyn Fn(i64),
+            is_cancelled: &dyn Fn() -> bool,
+        ) -> JobStatus {
+            if is_cancelled() {
+                return JobStatus::Cancelled;
+            }
+            self.record(MediaCall::Run(step.output.clone()));
+            if let Some(gate) = self.gated_outputs.get(&step.output) {
+                let _ = gate.lock().unwrap().recv();
+            }
+            if self.failing_outputs.contains(&step.output) {
+                return JobStatus::Failed(format!("could not write {:?}", step.output));
+            }
+            send_progress(step.duration_ms);
+            JobStatus::Succeeded
+        }
+    }
+}
@@ -0,0 +1,1431 @@
+use std::{
+    collections::BTreeMap,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ffmpeg::{CutMode, CutSettings, PreviewSettings};
+use crate::Cutmarks;
+
+/// Version of the project file format. Bump this whenever the layout of `Project` changes
+/// and add a migration for the old layout to `Project::migrate`.
+const PROJECT_FILE_VERSION: u64 = 2;
+const PROJECT_DIR_NAME: &str = ".vac";
+const PROJECT_FILE_NAME: &str = "project.json";
+const CLIPS_DIR_NAME: &str = "_clips";
+const REELS_DIR_NAME: &str = "_reels";
+const PREVIEWS_DIR_NAME: &str = "_previews";
+
+/// Longest pause before the repetition of a loop, see `LoopOptions::pause_seconds`
+const MAX_PAUSE_SECONDS: f64 = 3600.0;
+
+/// Separates the suffixes of several tags in a clip's file name
+const CLIP_SUFFIX_SEPARATOR: char = '-';
+
+/// A tag that clips can be marked with, e.g. "Offense" or "Red Zone".
+/// The suffix is appended to the file names of clips with this tag.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ClipTag {
+    pub(crate) name: String,
+    pub(crate) suffix: String,
+}
+
+impl ClipTag {
+    fn new(name: &str, suffix: &str) -> ClipTag {
+        ClipTag {
+            name: name.to_owned(),
+            suffix: suffix.to_owned(),
+        }
+    }
+
+    /// Tags of projects which do not define their own
+    pub(crate) fn defaults() -> Vec<ClipTag> {
+        vec![
+            ClipTag::new("Offense", "Off"),
+            ClipTag::new("Defense", "Def"),
+        ]
+    }
+}
+
+/// Football data of the play shown in a clip
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct PlayAnnotation {
+    pub(crate) down: Option<u8>,
+    /// Yards to go for a first down
+    pub(crate) distance: Option<u32>,
+    /// e.g. "OWN 25" or "OPP 10"
+    pub(crate) field_position: String,
+    pub(crate) formation: String,
+    pub(crate) play_call: String,
+    /// Yards gained (or lost if negative) by the play
+    pub(crate) result_yards: Option<i32>,
+    pub(crate) note: String,
+}
+
+impl PlayAnnotation {
+    /// e.g. "3rd & 7", or "3rd down" if the distance is unknown
+    pub(crate) fn down_and_distance(&self) -> Option<String> {
+        let ordinal = match self.down? {
+            1 => "1st",
+            2 => "2nd",
+            3 => "3rd",
+            _ => "4th",
+        };
+        Some(match self.distance {
+            Some(distance) => format!("{} & {}", ordinal, distance),
+            None => format!("{} down", ordinal),
+        })
+    }
+
+    /// Builds an annotation from the text fields of the annotation form.
+    /// Empty number fields are `None`, invalid ones are an error.
+    pub(crate) fn from_fields(
+        down: &str,
+        distance: &str,
+        field_position: &str,
+        formation: &str,
+        play_call: &str,
+        result_yards: &str,
+        note: &str,
+    ) -> Result<PlayAnnotation, String> {
+        fn parse_number<T: std::str::FromStr>(
+            value: &str,
+            name: &str,
+        ) -> Result<Option<T>, String> {
+            let value = value.trim();
+            if value.is_empty() {
+                return Ok(None);
+            }
+            value
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("{} '{}' is not a valid number", name, value))
+        }
+
+        let down: Option<u8> = parse_number(down, "down")?;
+        if let Some(down) = down {
+            if !(1..=4).contains(&down) {
+                return Err(format!("down must be between 1 and 4, not {}", down));
+            }
+        }
+        Ok(PlayAnnotation {
+            down,
+            distance: parse_number(distance, "distance")?,
+            field_position: field_position.trim().to_owned(),
+            formation: formation.trim().to_owned(),
+            play_call: play_call.trim().to_owned(),
+            result_yards: parse_number(result_yards, "result yards")?,
+            note: note.trim().to_owned(),
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Clip {
+    /// End of the clip in ms. Unknown for clips from project files of version 1.
+    pub(crate) end: Option<i64>,
+
+    /// Names of the clip's tags
+    pub(crate) tags: Vec<String>,
+
+    #[serde(default)]
+    pub(crate) annotation: PlayAnnotation,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct MediaMetadata {
+    /// Clips keyed by their start in ms
+    pub(crate) clips: BTreeMap<i64, Clip>,
+    pub(crate) cutmarks: Option<Box<Cutmarks>>,
+}
+
+/// A clip as it is played when reviewing clips, see `Project::review_clips`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ReviewClip {
+    pub(crate) media_path: PathBuf,
+    pub(crate) start: i64,
+    pub(crate) end: i64,
+}
+
+/// A clip of a reel, referring to a clip of the project
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ReelClip {
+    /// File name of the video within the project directory
+    pub(crate) media: String,
+    /// Start of the clip in ms
+    pub(crate) start: i64,
+}
+
+/// How the clips of a reel are presented
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct ReelOptions {
+    /// Show a card with the number, tags, down & distance and game before each clip
+    pub(crate) title_cards: bool,
+    pub(crate) title_card_seconds: f64,
+    /// Burn the same information into the bottom of each clip
+    pub(crate) captions: bool,
+    /// Font for title cards and captions. Needed if ffmpeg is built without fontconfig.
+    pub(crate) font_file: Option<String>,
+}
+
+impl Default for ReelOptions {
+    fn default() -> Self {
+        ReelOptions {
+            title_cards: false,
+            title_card_seconds: 3.0,
+            captions: false,
+            font_file: None,
+        }
+    }
+}
+
+/// Texts shown with a clip of a reel
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ReelClipTexts {
+    pub(crate) title_lines: Vec<String>,
+    pub(crate) caption: String,
+}
+
+/// A named selection of clips in a chosen order, e.g. for a player meeting
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Reel {
+    pub(crate) name: String,
+    pub(crate) clips: Vec<ReelClip>,
+    #[serde(default)]
+    pub(crate) options: ReelOptions,
+}
+
+impl Reel {
+    /// File name of the rendered reel without extension
+    pub(crate) fn file_stem(&self) -> String {
+        file_name_safe(&self.name)
+    }
+}
+
+/// A video of a game filmed from one position, e.g. the sideline or the endzone
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Angle {
+    /// File name of the video within the project directory
+    pub(crate) media: String,
+    /// Time of this video in ms at which the other angles of the group are at their own offset,
+    /// e.g. the time of the kickoff
+    #[serde(default)]
+    pub(crate) offset_ms: i64,
+}
+
+/// The videos of one game filmed from different angles. They are played and cut in sync.
+/// The first angle is the one that is opened, the other ones follow it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct AngleGroup {
+    pub(crate) name: String,
+    pub(crate) angles: Vec<Angle>,
+}
+
+/// What happens after a loop has been played as often as set in `LoopOptions::repeat`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub(crate) enum AfterLoop {
+    /// Plays on after the end of the loop
+    Continue,
+    /// Leaves the loop and jumps to the next clip
+    NextClip,
+    /// Leaves the loop and jumps to the next cutmark
+    NextCutmark,
+}
+
+/// How a loop is played back, e.g. to show a play to the team several times
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct LoopOptions {
+    /// How often the loop is played, 0 repeats it until it is broken
+    pub(crate) repeat: u32,
+    pub(crate) after: AfterLoop,
+    /// Every second repetition is played at this rate instead of the current speed
+    pub(crate) slow_motion_rate: Option<f32>,
+    /// Pause before every repetition
+    pub(crate) pause_seconds: f64,
+}
+
+impl Default for LoopOptions {
+    fn default() -> Self {
+        LoopOptions {
+            repeat: 0,
+            after: AfterLoop::Continue,
+            slow_motion_rate: None,
+            pause_seconds: 0.0,
+        }
+    }
+}
+
+impl std::fmt::Display for LoopOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.repeat == 0 {
+            f.write_str("loop forever")?;
+        } else {
+            let after = match self.after {
+                AfterLoop::Continue => "play on",
+                AfterLoop::NextClip => "next clip",
+                AfterLoop::NextCutmark => "next cutmark",
+            };
+            write!(f, "loop {}x, then {}", self.repeat, after)?;
+        }
+        if let Some(rate) = self.slow_motion_rate {
+            write!(f, ", every 2nd at {}x", rate)?;
+        }
+        if self.pause_seconds > 0.0 {
+            write!(f, ", {} s pause", self.pause_seconds)?;
+        }
+        Ok(())
+    }
+}
+
+/// The loop options to choose from with `CycleLoopOptions`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct LoopSettings {
+    pub(crate) presets: Vec<LoopOptions>,
+    /// Index of the options in `presets` that are used
+    pub(crate) active: usize,
+}
+
+impl Default for LoopSettings {
+    fn default() -> Self {
+        LoopSettings {
+            presets: vec![
+                LoopOptions::default(),
+                LoopOptions {
+                    repeat: 3,
+                    ..Default::default()
+                },
+                LoopOptions {
+                    repeat: 4,
+                    after: AfterLoop::NextClip,
+                    slow_motion_rate: Some(0.25),
+                    pause_seconds: 2.0,
+                },
+            ],
+            active: 0,
+        }
+    }
+}
+
+/// State of a project directory that has to survive a restart of VAC.
+/// It is stored as `.vac/project.json` inside the project directory.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Project {
+    version: u64,
+
+    /// Tags that clips of this project can be marked with
+    #[serde(default = "ClipTag::defaults")]
+    tags: Vec<ClipTag>,
+
+    /// How clips are cut
+    #[serde(default)]
+    cut: CutSettings,
+
+    /// How animations and contact sheets of clips look
+    #[serde(default)]
+    preview: PreviewSettings,
+
+    /// How loops are played back
+    #[serde(default)]
+    loops: LoopSettings,
+
+    /// Metadata of every video in the project, keyed by its file name within the project directory
+    media: BTreeMap<String, MediaMetadata>,
+
+    #[serde(default)]
+    reels: Vec<Reel>,
+
+    /// Videos of the same game from different angles
+    #[serde(default)]
+    angles: Vec<AngleGroup>,
+
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+impl Project {
+    fn new(dir: &Path) -> Project {
+        Project {
+            version: PROJECT_FILE_VERSION,
+            tags: ClipTag::defaults(),
+            cut: CutSettings::default(),
+            preview: PreviewSettings::default(),
+            loops: LoopSettings::default(),
+            media: BTreeMap::new(),
+            reels: Vec::new(),
+            angles: Vec::new(),
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    pub(crate) fn file_path(dir: &Path) -> PathBuf {
+        dir.join(PROJECT_DIR_NAME).join(PROJECT_FILE_NAME)
+    }
+
+    /// Loads the project file of `dir` or creates an empty project if there is none yet.
+    pub(crate) fn load(dir: &Path) -> Result<Project, Error> {
+        let file_path = Project::file_path(dir);
+        if !file_path.exists() {
+            return Ok(Project::new(dir));
+        }
+
+        let content = std::fs::read_to_string(&file_path)?;
+        let value: serde_json::Value = serde_json::from_str(&content)?;
+        let version = value["version"].as_u64().unwrap_or(0);
+        if version > PROJECT_FILE_VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "{:?} has version {} but this VAC only supports up to version {}",
+                    file_path, version, PROJECT_FILE_VERSION
+                ),
+            ));
+        }
+
+        let mut project: Project = serde_json::from_value(Project::migrate(value, version))?;
+        if let Err(e) = project
+            .validate_tags()
+            .and_then(|_| project.validate_angles())
+            .and_then(|_| project.validate_loops())
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("{:?}: {}", file_path, e),
+            ));
+        }
+        project.version = PROJECT_FILE_VERSION;
+        project.dir = dir.to_path_buf();
+        Ok(project)
+    }
+
+    /// Converts the content of a project file of an older version to the current layout
+    fn migrate(mut value: serde_json::Value, version: u64) -> serde_json::Value {
+        if version < 2 {
+            // Version 1 only stored the start times of clips
+            if let Some(media) = value["media"].as_object_mut() {
+                for metadata in media.values_mut() {
+                    let clips: serde_json::Map<String, serde_json::Value> = metadata["clips"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .map(|start| {
+                            (
+                                start.to_string(),
+                                serde_json::to_value(Clip::default()).unwrap(),
+                            )
+                        })
+                        .collect();
+                    metadata["clips"] = serde_json::Value::Object(clips);
+                }
+            }
+        }
+
+        value
+    }
+
+    fn validate_tags(&self) -> Result<(), String> {
+        for (i, tag) in self.tags.iter().enumerate() {
+            if tag.name.is_empty() || tag.name.contains('+') {
+                return Err(format!(
+                    "tag name \"{}\" must not be empty or contain '+'",
+                    tag.name
+                ));
+            }
+            if tag.suffix.is_empty() || !tag.suffix.chars().all(|c| c.is_ascii_alphabetic()) {
+                return Err(format!(
+                    "suffix \"{}\" of tag \"{}\" must consist of letters only",
+                    tag.suffix, tag.name
+                ));
+            }
+            if let Some(other) = self.tags[..i]
+                .iter()
+                .find(|other| other.name == tag.name || other.suffix == tag.suffix)
+            {
+                return Err(format!(
+                    "tags \"{}\" and \"{}\" must have different names and suffixes",
+                    other.name, tag.name
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_angles(&self) -> Result<(), String> {
+        let mut grouped: Vec<&str> = Vec::new();
+        for group in self.angles.iter() {
+            if group.angles.is_empty() {
+                return Err(format!("angle group \"{}\" has no videos", group.name));
+            }
+            for angle in group.angles.iter() {
+                if grouped.contains(&angle.media.as_str()) {
+                    return Err(format!(
+                        "video \"{}\" must not be in more than one angle group",
+                        angle.media
+                    ));
+                }
+                grouped.push(&angle.media);
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_loops(&self) -> Result<(), String> {
+        for (i, options) in self.loops.presets.iter().enumerate() {
+            if !(options.pause_seconds >= 0.0 && options.pause_seconds <= MAX_PAUSE_SECONDS) {
+                return Err(format!(
+                    "pause of {} seconds of loop option #{} \"{}\" must be between 0 and {}",
+                    options.pause_seconds,
+                    i + 1,
+                    options,
+                    MAX_PAUSE_SECONDS
+                ));
+            }
+            if let Some(rate) = options.slow_motion_rate {
+                if !(rate > 0.0 && rate.is_finite()) {
+                    return Err(format!(
+                        "slow motion rate {} of loop option #{} \"{}\" must be greater than 0",
+                        rate,
+                        i + 1,
+                        options
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the project file. The content is written to a temporary file first and then
+    /// renamed, so a crash while saving never leaves a truncated project file behind.
+    pub(crate) fn save(&self) -> Result<(), Error> {
+        let file_path = Project::file_path(&self.dir);
+        std::fs::create_dir_all(file_path.parent().unwrap())?;
+
+        let tmp_file_path = file_path.with_extension("json.tmp");
+        std::fs::write(&tmp_file_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_file_path, &file_path)
+    }
+
+    pub(crate) fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Directory for the project file and temporary files of VAC
+    pub(crate) fn work_dir(&self) -> PathBuf {
+        self.dir.join(PROJECT_DIR_NAME)
+    }
+
+    /// Directory the clips of all videos of the project are cut into
+    pub(crate) fn clips_dir(&self) -> PathBuf {
+        self.dir.join(CLIPS_DIR_NAME)
+    }
+
+    /// Path of the file a clip is cut into. Unknown tags are left out of the file name.
+    pub(crate) fn clip_file_path(&self, media_path: &Path, start: i64, clip: &Clip) -> PathBuf {
+        let tags: Vec<ClipTag> = clip
+            .tags
+            .iter()
+            .filter_map(|name| self.tags.iter().find(|tag| tag.name == *name).cloned())
+            .collect();
+        self.clips_dir()
+            .join(clip_file_name(media_path, start, &tags))
+    }
+
+    /// Directory the reels of the project are rendered into
+    pub(crate) fn reels_dir(&self) -> PathBuf {
+        self.dir.join(REELS_DIR_NAME)
+    }
+
+    /// Directory the animations and contact sheets of clips are written to
+    pub(crate) fn previews_dir(&self) -> PathBuf {
+        self.dir.join(PREVIEWS_DIR_NAME)
+    }
+
+    pub(crate) fn tags(&self) -> &[ClipTag] {
+        &self.tags
+    }
+
+    pub(crate) fn cut_settings(&self) -> &CutSettings {
+        &self.cut
+    }
+
+    pub(crate) fn preview_settings(&self) -> &PreviewSettings {
+        &self.preview
+    }
+
+    pub(crate) fn set_cut_mode(&mut self, mode: CutMode) {
+        self.cut.mode = mode;
+    }
+
+    /// The chosen loop options, or the default ones if there are no presets
+    pub(crate) fn loop_options(&self) -> LoopOptions {
+        self.loops
+            .presets
+            .get(self.loops.active)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Chooses the next loop options of the presets and returns them
+    pub(crate) fn next_loop_options(&mut self) -> LoopOptions {
+        if !self.loops.presets.is_empty() {
+            self.loops.active = (self.loops.active + 1) % self.loops.presets.len();
+        }
+        self.loop_options()
+    }
+
+    /// Looks up the tags with the given names
+    pub(crate) fn find_tags(&self, names: &[String]) -> Result<Vec<ClipTag>, String> {
+        names
+            .iter()
+            .map(|name| {
+                self.tags
+                    .iter()
+                    .find(|tag| tag.name == *name)
+                    .cloned()
+                    .ok_or_else(|| format!("unknown tag \"{}\"", name))
+            })
+            .collect()
+    }
+
+    /// The file name a video is stored under in the project
+    pub(crate) fn media_key(media_path: &Path) -> Option<String> {
+        Some(media_path.file_name()?.to_str()?.to_owned())
+    }
+
+    /// Whether `media_path` is in the project directory. Videos are stored by their file name,
+    /// so a video of the same name in another directory would otherwise be taken for it.
+    pub(crate) fn contains_media(&self, media_path: &Path) -> bool {
+        media_path.parent() == Some(self.dir.as_path())
+    }
+
+    /// Iterates over the paths and metadata of all videos in the project
+    pub(crate) fn media(&self) -> impl Iterator<Item = (PathBuf, &MediaMetadata)> {
+        self.media
+            .iter()
+            .map(move |(file_name, metadata)| (self.dir.join(file_name), metadata))
+    }
+
+    /// The clips with all of the given tags in the order they are reviewed: by video and start.
+    /// Clips of angles following another one show the same plays and are left out, as are
+    /// clips whose end is unknown.
+    pub(crate) fn review_clips(&self, tag_names: &[String]) -> Vec<ReviewClip> {
+        let mut review_clips = Vec::new();
+        for (media_path, metadata) in self.media() {
+            if self.is_following_angle(&media_path) {
+                continue;
+            }
+            for (start, clip) in metadata.clips.iter() {
+                if !tag_names.iter().all(|name| clip.tags.contains(name)) {
+                    continue;
+                }
+                match clip.end {
+                    Some(end) => review_clips.push(ReviewClip {
+                        media_path: media_path.clone(),
+                        start: *start,
+                        end,
+                    }),
+                    None => println!(
+                        "cannot review the clip at {} of {:?}, its end is unknown",
+                        format_timecode(*start),
+                        media_path
+                    ),
+                }
+            }
+        }
+        review_clips
+    }
+
+    pub(crate) fn media_metadata(&self, media_path: &Path) -> Option<&MediaMetadata> {
+        self.media.get(&Project::media_key(media_path)?)
+    }
+
+    pub(crate) fn media_metadata_mut(&mut self, media_path: &Path) -> Option<&mut MediaMetadata> {
+        let key = Project::media_key(media_path)?;
+        Some(self.media.entry(key).or_default())
+    }
+
+    pub(crate) fn reels(&self) -> &[Reel] {
+        &self.reels
+    }
+
+    pub(crate) fn reels_mut(&mut self) -> &mut Vec<Reel> {
+        &mut self.reels
+    }
+
+    pub(crate) fn angle_groups(&self) -> &[AngleGroup] {
+        &self.angles
+    }
+
+    /// Paths of the videos of an angle group, the first angle first
+    pub(crate) fn angle_media_paths(&self, group: &AngleGroup) -> Vec<PathBuf> {
+        group
+            .angles
+            .iter()
+            .map(|angle| self.dir.join(&angle.media))
+            .collect()
+    }
+
+    /// The offset of the video within its angle group
+    pub(crate) fn angle_offset_mut(&mut self, media_path: &Path) -> Option<&mut i64> {
+        let key = Project::media_key(media_path)?;
+        self.angles
+            .iter_mut()
+            .flat_map(|group| group.angles.iter_mut())
+            .find(|angle| angle.media == key)
+            .map(|angle| &mut angle.offset_ms)
+    }
+
+    fn angle_group(&self, media_path: &Path) -> Option<(&AngleGroup, &Angle)> {
+        let key = Project::media_key(media_path)?;
+        self.angles.iter().find_map(|group| {
+            group
+                .angles
+                .iter()
+                .find(|angle| angle.media == key)
+                .map(|angle| (group, angle))
+        })
+    }
+
+    /// Whether the video is an angle that only follows the first angle of its group.
+    /// Such videos are not opened on their own.
+    pub(crate) fn is_following_angle(&self, media_path: &Path) -> bool {
+        match self.angle_group(media_path) {
+            Some((group, angle)) => !std::ptr::eq(angle, &group.angles[0]),
+            None => false,
+        }
+    }
+
+    /// The other angles of the video's group with their offsets to the video, i.e. a time `t`
+    /// of the video corresponds to `t + offset` of the angle
+    pub(crate) fn other_angles(&self, media_path: &Path) -> Vec<(PathBuf, i64)> {
+        match self.angle_group(media_path) {
+            Some((group, this)) => group
+                .angles
+                .iter()
+                .filter(|angle| !std::ptr::eq(*angle, this))
+                .map(|angle| {
+                    (
+                        self.dir.join(&angle.media),
+                        angle.offset_ms - this.offset_ms,
+                    )
+                })
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Iterates over all clips of the project as they can be added to a reel
+    pub(crate) fn reel_clips(&self) -> impl Iterator<Item = (ReelClip, &Clip)> {
+        self.media.iter().flat_map(|(file_name, metadata)| {
+            metadata.clips.iter().map(move |(start, clip)| {
+                (
+                    ReelClip {
+                        media: file_name.clone(),
+                        start: *start,
+                    },
+                    clip,
+                )
+            })
+        })
+    }
+
+    pub(crate) fn reel_clip(&self, reel_clip: &ReelClip) -> Option<&Clip> {
+        self.media
+            .get(&reel_clip.media)?
+            .clips
+            .get(&reel_clip.start)
+    }
+
+    /// Texts for the title card and caption of every clip of a reel, e.g. the title lines
+    /// "Clip 3 / 12", "Offense", "3rd & 7, OWN 25", "game1" and the caption
+    /// "3/12 | Offense | 3rd & 7, OWN 25 | game1"
+    pub(crate) fn reel_clip_texts(&self, reel: &Reel) -> Vec<ReelClipTexts> {
+        let total = reel.clips.len();
+        reel.clips
+            .iter()
+            .enumerate()
+            .map(|(i, reel_clip)| {
+                let game = Path::new(&reel_clip.media)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let (tags, situation) = match self.reel_clip(reel_clip) {
+                    Some(clip) => {
+                        let annotation = &clip.annotation;
+                        let situation: Vec<String> = annotation
+                            .down_and_distance()
+                            .into_iter()
+                            .chain(Some(annotation.field_position.clone()))
+                            .filter(|text| !text.is_empty())
+                            .collect();
+                        (clip.tags.join(", "), situation.join(", "))
+                    }
+                    None => (String::new(), String::new()),
+                };
+
+                let title_lines: Vec<String> =
+                    vec![format!("Clip {} / {}", i + 1, total), tags, situation, game]
+                        .into_iter()
+                        .filter(|line| !line.is_empty())
+                        .collect();
+                let mut caption_parts = title_lines.clone();
+                caption_parts[0] = format!("{}/{}", i + 1, total);
+                ReelClipTexts {
+                    title_lines,
+                    caption: caption_parts.join(" | "),
+                }
+            })
+            .collect()
+    }
+
+    /// Paths of the cut clip files of a reel in the order of the reel
+    pub(crate) fn reel_clip_files(&self, reel: &Reel) -> Result<Vec<PathBuf>, String> {
+        reel.clips
+            .iter()
+            .map(|reel_clip| {
+                let clip = self.reel_clip(reel_clip).ok_or_else(|| {
+                    format!(
+                        "the clip of {} at {} does not exist anymore",
+                        reel_clip.media,
+                        format_timecode(reel_clip.start)
+                    )
+                })?;
+                let path =
+                    self.clip_file_path(&self.dir.join(&reel_clip.media), reel_clip.start, clip);
+                if path.exists() {
+                    Ok(path)
+                } else {
+                    Err(format!("the clip {:?} has not been cut", path))
+                }
+            })
+            .collect()
+    }
+
+    /// Adds the clips in the clips directory which the project file does not know (yet), e.g.
+    /// because they were cut by an older VAC, and completes clips without an end.
+    /// `probe_duration` returns the duration of a clip file in ms. Files in `skip` are ignored.
+    /// Returns the number of changed clips and a message for every file that could not be used.
+    pub(crate) fn recover_clips(
+        &mut self,
+        skip: &[PathBuf],
+        probe_duration: impl Fn(&Path) -> Result<i64, String>,
+    ) -> (usize, Vec<String>) {
+        let (unknown_clips, mut problems) = self.unknown_clips(skip);
+        let probed_clips = unknown_clips
+            .into_iter()
+            .map(|clip| {
+                let duration = probe_duration(&clip.file);
+                (clip, duration)
+            })
+            .collect();
+        let (changed, probe_problems) = self.add_recovered_clips(skip, probed_clips);
+        problems.extend(probe_problems);
+        (changed, problems)
+    }
+
+    /// The first part of `recover_clips`: finds the clip files which have to be probed, and
+    /// returns them with a message for every file that cannot be used
+    pub(crate) fn unknown_clips(&self, skip: &[PathBuf]) -> (Vec<UnknownClip>, Vec<String>) {
+        let clips_dir = self.clips_dir();
+        if !clips_dir.exists() {
+            return (Vec::new(), Vec::new());
+        }
+        let clip_files = match crate::list_media(&clips_dir) {
+            Ok(clip_files) => clip_files,
+            Err(e) => {
+                return (
+                    Vec::new(),
+                    vec![format!("could not read {:?}: {}", clips_dir, e)],
+                )
+            }
+        };
+
+        let mut unknown_clips = Vec::new();
+        let mut problems = Vec::new();
+        for clip_file in clip_files.into_iter().filter(|file| !skip.contains(file)) {
+            let file_name = clip_file.file_name().unwrap().to_string_lossy();
+            let parsed = match parse_clip_file_name(&file_name) {
+                Some(parsed) => parsed,
+                None => {
+                    problems.push(format!("{}: not a clip file name", file_name));
+                    continue;
+                }
+            };
+            let tags: Option<Vec<String>> = parsed
+                .suffixes
+                .iter()
+                .map(|suffix| {
+                    self.tags
+                        .iter()
+                        .find(|tag| tag.suffix == *suffix)
+                        .map(|tag| tag.name.clone())
+                })
+                .collect();
+            let tags = match tags {
+                Some(tags) => tags,
+                None => {
+                    problems.push(format!("{}: unknown tag suffix", file_name));
+                    continue;
+                }
+            };
+
+            if !self.is_complete_clip(&parsed.media_file_name, parsed.start) {
+                unknown_clips.push(UnknownClip {
+                    file: clip_file.clone(),
+                    media_file_name: parsed.media_file_name,
+                    start: parsed.start,
+                    tags,
+                });
+            }
+        }
+        (unknown_clips, problems)
+    }
+
+    /// The second part of `recover_clips`: adds the clips found by `unknown_clips` with the
+    /// durations of their files in ms. Clips which were completed or are in `skip` by now are
+    /// left alone.
+    pub(crate) fn add_recovered_clips(
+        &mut self,
+        skip: &[PathBuf],
+        probed_clips: Vec<(UnknownClip, Result<i64, String>)>,
+    ) -> (usize, Vec<String>) {
+        let mut changed = 0;
+        let mut problems = Vec::new();
+        for (unknown_clip, duration) in probed_clips {
+            if skip.contains(&unknown_clip.file)
+                || self.is_complete_clip(&unknown_clip.media_file_name, unknown_clip.start)
+            {
+                continue;
+            }
+            let end = match duration {
+                Ok(duration) => Some(unknown_clip.start + duration),
+                Err(e) => {
+                    let file_name = unknown_clip.file.file_name().unwrap_or_default();
+                    problems.push(format!("{}: {}", file_name.to_string_lossy(), e));
+                    None
+                }
+            };
+            let clips = &mut self
+                .media
+                .entry(unknown_clip.media_file_name)
+                .or_default()
+                .clips;
+            let tags = unknown_clip.tags;
+            let clip = clips.entry(unknown_clip.start).or_insert_with(|| Clip {
+                tags,
+                ..Default::default()
+            });
+            clip.end = end;
+            changed += 1;
+        }
+        (changed, problems)
+    }
+
+    fn is_complete_clip(&self, media_file_name: &str, start: i64) -> bool {
+        let clip = self
+            .media
+            .get(media_file_name)
+            .and_then(|metadata| metadata.clips.get(&start));
+        matches!(clip, Some(Clip { end: Some(_), .. }))
+    }
+}
+
+/// Turns a name into a lowercase file name, e.g. "Red Zone" into `red_zone`
+pub(crate) fn file_name_safe(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Formats a time in ms as `h:mm:ss.mmm`
+pub(crate) fn format_timecode(time_ms: i64) -> String {
+    let ms = time_ms % 1000;
+    let s = time_ms / 1000 % 60;
+    let min = time_ms / 60_000 % 60;
+    let h = time_ms / 3_600_000;
+    format!("{}:{:02}:{:02}.{:03}", h, min, s, ms)
+}
+
+/// File name of a clip: `<video file name>_<start in ms, 8 digits><tag suffixes>.<video extension>`.
+/// The suffixes of several tags are separated by '-', e.g. `game.mp4_00016101Off-RZ.mp4`.
+pub(crate) fn clip_file_name(media_path: &Path, start: i64, tags: &[ClipTag]) -> String {
+    assert!(start >= 0 && start < i64::pow(10, 8));
+
+    let suffixes: Vec<&str> = tags.iter().map(|tag| tag.suffix.as_str()).collect();
+    // timestamp formatted with 8 digits to be able to store 24h.
+    let mut file_name = format!(
+        "{}_{:0>8}{}",
+        media_path.file_name().unwrap().to_str().unwrap(),
+        start,
+        suffixes.join(&CLIP_SUFFIX_SEPARATOR.to_string())
+    );
+    if let Some(extension) = media_path.extension().and_then(|ext| ext.to_str()) {
+        file_name = file_name + "." + extension;
+    }
+    file_name
+}
+
+/// A clip file which the project file does not know (yet), see `Project::unknown_clips`
+#[derive(Debug)]
+pub(crate) struct UnknownClip {
+    pub(crate) file: PathBuf,
+    media_file_name: String,
+    start: i64,
+    tags: Vec<String>,
+}
+
+/// The parts of a clip's file name as created by `clip_file_name`
+#[derive(Debug, PartialEq)]
+pub(crate) struct ClipFileName {
+    /// File name of the video the clip was cut from
+    pub(crate) media_file_name: String,
+    pub(crate) start: i64,
+    pub(crate) suffixes: Vec<String>,
+}
+
+/// Parses the file name of a clip, e.g. `game.mp4_00016101Off-RZ.mp4`
+pub(crate) fn parse_clip_file_name(file_name: &str) -> Option<ClipFileName> {
+    let stem = Path::new(file_name).file_stem()?.to_str()?;
+    let separator = stem.rfind('_')?;
+    let media_file_name = &stem[..separator];
+    let timestamp_and_suffixes = &stem[separator + 1..];
+    if media_file_name.is_empty() || timestamp_and_suffixes.len() < 8 {
+        return None;
+    }
+
+    let (timestamp, suffixes) = timestamp_and_suffixes.split_at(8);
+    if !timestamp.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let suffixes: Vec<String> = if suffixes.is_empty() {
+        Vec::new()
+    } else {
+        suffixes
+            .split(CLIP_SUFFIX_SEPARATOR)
+            .map(|suffix| suffix.to_owned())
+            .collect()
+    };
+    if suffixes
+        .iter()
+        .any(|suffix| suffix.is_empty() || !suffix.chars().all(|c| c.is_ascii_alphabetic()))
+    {
+        return None;
+    }
+
+    Some(ClipFileName {
+        media_file_name: media_file_name.to_owned(),
+        start: timestamp.parse().ok()?,
+        suffixes,
+    })
+}
+
+/// Extracts the tag suffixes from the file stem of a clip as created by `clip_file_name`
+pub(crate) fn clip_tag_suffixes(file_stem: &str) -> Vec<&str> {
+    let timestamp_and_suffixes = match file_stem.rfind('_') {
+        Some(i) => &file_stem[i + 1..],
+        None => file_stem,
+    };
+    timestamp_and_suffixes
+        .trim_start_matches(|c: char| c.is_ascii_digit())
+        .split(CLIP_SUFFIX_SEPARATOR)
+        .filter(|suffix| !suffix.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_project_roundtrip() {
+        let project_dir = Path::new("tests").join("output").join("project_roundtrip");
+        let _ = std::fs::remove_dir_all(&project_dir);
+        std::fs::create_dir_all(&project_dir).unwrap();
+
+        let media_path = project_dir.join("game.mp4");
+        let mut project = Project::load(&project_dir).unwrap();
+        assert!(project.media_metadata(&media_path).is_none());
+
+        let metadata = project.media_metadata_mut(&media_path).unwrap();
+        metadata.clips.insert(
+            3601,
+            Clip {
+                end: Some(9000),
+                tags: vec!["Offense".to_owned()],
+                annotation: PlayAnnotation {
+                    down: Some(3),
+                    distance: Some(7),
+                    formation: "Trips Right".to_owned(),
+                    result_yards: Some(-2),
+                    ..Default::default()
+                },
+            },
+        );
+        metadata.clips.insert(16101, Clip::default());
+        metadata.cutmarks = Some(Box::new([100, 2000].iter().copied().collect()));
+        project.save().unwrap();
+
+        let loaded = Project::load(&project_dir).unwrap();
+        assert_eq!(
+            loaded.media_metadata(&media_path),
+            project.media_metadata(&media_path)
+        );
+        assert_eq!(loaded.tags(), ClipTag::defaults().as_slice());
+    }
+
+    #[test]
+    fn test_migrate_version_1() {
+        let v1 = serde_json::json!({
+            "version": 1,
+            "media": { "game.mp4": { "clips": [3601, 16101], "cutmarks": null } }
+        });
+        let project: Project = serde_json::from_value(Project::migrate(v1, 1)).unwrap();
+        let clips = &project.media["game.mp4"].clips;
+        assert_eq!(clips.keys().copied().collect::<Vec<_>>(), vec![3601, 16101]);
+        assert_eq!(clips[&3601], Clip::default());
+    }
+
+    #[test]
+    fn test_loop_options() {
+        let mut project = Project::new(Path::new("."));
+        assert_eq!(project.loop_options(), LoopOptions::default());
+        assert_eq!(project.loop_options().to_string(), "loop forever");
+        assert_eq!(project.next_loop_options().repeat, 3);
+        let teaching = project.next_loop_options();
+        assert_eq!(
+            teaching.to_string(),
+            "loop 4x, then next clip, every 2nd at 0.25x, 2 s pause"
+        );
+        assert_eq!(project.next_loop_options(), LoopOptions::default());
+
+        // the chosen options are stored, missing fields are defaults
+        let project: Project = serde_json::from_value(serde_json::json!({
+            "version": 2,
+            "media": {},
+            "loops": { "presets": [{ "repeat": 2, "after": "NextCutmark" }], "active": 0 }
+        }))
+        .unwrap();
+        let options = project.loop_options();
+        assert_eq!(options.after, AfterLoop::NextCutmark);
+        assert_eq!(options.slow_motion_rate, None);
+        assert_eq!(options.pause_seconds, 0.0);
+
+        let project_dir = Path::new("tests")
+            .join("output")
+            .join("project_loop_options");
+        let _ = std::fs::remove_dir_all(&project_dir);
+        std::fs::create_dir_all(project_dir.join(PROJECT_DIR_NAME)).unwrap();
+        for preset in [
+            serde_json::json!({ "pause_seconds": -1.0 }),
+            serde_json::json!({ "pause_seconds": 1e300 }),
+            serde_json::json!({ "slow_motion_rate": 0.0 }),
+        ]
+        .iter()
+        {
+            let content = serde_json::json!({
+                "version": 2,
+                "media": {},
+                "loops": { "presets": [preset], "active": 0 }
+            });
+            std::fs::write(Project::file_path(&project_dir), content.to_string()).unwrap();
+            assert!(Project::load(&project_dir).is_err(), "{} is valid", preset);
+        }
+
+        // the error names the invalid option
+        let content = serde_json::json!({
+            "version": 2,
+            "media": {},
+            "loops": { "presets": [{}, { "slow_motion_rate": -0.5 }], "active": 0 }
+        });
+        std::fs::write(Project::file_path(&project_dir), content.to_string()).unwrap();
+        let error = Project::load(&project_dir).unwrap_err().to_string();
+        assert!(error.contains("loop option #2"), "{}", error);
+
+        std::fs::remove_dir_all(&project_dir).unwrap();
+    }
+
+    #[test]
+    fn test_clip_file_names() {
+        let tags = vec![
+            ClipTag::new("Offense", "Off"),
+            ClipTag::new("Red Zone", "RZ"),
+        ];
+        let name = clip_file_name(Path::new("games/game.mp4"), 16101, &tags);
+        assert_eq!(name, "game.mp4_00016101Off-RZ.mp4");
+        assert_eq!(
+            clip_tag_suffixes(Path::new(&name).file_stem().unwrap().to_str().unwrap()),
+            vec!["Off", "RZ"]
+        );
+        assert_eq!(
+            clip_tag_suffixes("testvideo.mp4_00000851"),
+            Vec::<&str>::new()
+        );
+        assert_eq!(clip_tag_suffixes("100000Def"), vec!["Def"]);
+
+        assert_eq!(
+            parse_clip_file_name(&name),
+            Some(ClipFileName {
+                media_file_name: "game.mp4".to_owned(),
+                start: 16101,
+                suffixes: vec!["Off".to_owned(), "RZ".to_owned()],
+            })
+        );
+        assert_eq!(
+            parse_clip_file_name("my_game.mp4_00000851.mp4").map(|parsed| parsed.start),
+            Some(851)
+        );
+        assert_eq!(parse_clip_file_name("game.mp4_851Off.mp4"), None);
+        assert_eq!(parse_clip_file_name("game.mp4_00000851.head.mp4"), None);
+        assert_eq!(parse_clip_file_name("condensed_all.mp4"), None);
+    }
+
+    #[test]
+    fn test_annotation_from_fields() {
+        let annotation =
+            PlayAnnotation::from_fields("3", " 7", "OWN 25", "Trips Right", "Slant", "-2", "")
+                .unwrap();
+        assert_eq!(annotation.down, Some(3));
+        assert_eq!(annotation.distance, Some(7));
+        assert_eq!(annotation.result_yards, Some(-2));
+        assert_eq!(annotation.down_and_distance().as_deref(), Some("3rd & 7"));
+
+        let empty = PlayAnnotation::from_fields("", "", "", "", "", "", "").unwrap();
+        assert_eq!(empty, PlayAnnotation::default());
+
+        assert!(PlayAnnotation::from_fields("7", "", "", "", "", "", "").is_err());
+        assert!(PlayAnnotation::from_fields("0", "", "", "", "", "", "").is_err());
+        assert!(PlayAnnotation::from_fields("", "-3", "", "", "", "", "").is_err());
+        assert!(PlayAnnotation::from_fields("", "", "", "", "", "abc", "").is_err());
+    }
+
+    #[test]
+    fn test_reel_clip_files() {
+        let project_dir = Path::new("tests").join("output").join("project_reels");
+        let _ = std::fs::remove_dir_all(&project_dir);
+        std::fs::create_dir_all(project_dir.join(CLIPS_DIR_NAME)).unwrap();
+
+        let mut project = Project::load(&project_dir).unwrap();
+        for (game, start) in [("game2.mp4", 851), ("game1.mp4", 3601)].iter() {
+            let metadata = project.media_metadata_mut(&project_dir.join(game)).unwrap();
+            metadata.clips.insert(*start, Clip::default());
+        }
+        std::fs::File::create(
+            project_dir
+                .join(CLIPS_DIR_NAME)
+                .join("game2.mp4_00000851Off.mp4"),
+        )
+        .unwrap();
+        std::fs::File::create(
+            project_dir
+                .join(CLIPS_DIR_NAME)
+                .join("game1.mp4_00003601.mp4"),
+        )
+        .unwrap();
+
+        // the order of the reel, not the order of the videos
+        project
+            .media_metadata_mut(&project_dir.join("game2.mp4"))
+            .unwrap()
+            .clips
+            .insert(
+                851,
+                Clip {
+                    tags: vec!["Offense".to_owned()],
+                    annotation: PlayAnnotation {
+                        down: Some(3),
+                        distance: Some(7),
+                        field_position: "OWN 25".to_owned(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            );
+        let mut reel = Reel {
+            name: "3rd Down / Cover 2".to_owned(),
+            clips: vec![
+                ReelClip {
+                    media: "game2.mp4".to_owned(),
+                    start: 851,
+                },
+                ReelClip {
+                    media: "game1.mp4".to_owned(),
+                    start: 3601,
+                },
+            ],
+            options: ReelOptions::default(),
+        };
+        assert_eq!(reel.file_stem(), "3rd_down___cover_2");
+        let texts = project.reel_clip_texts(&reel);
+        assert_eq!(
+            texts[0].title_lines,
+            vec!["Clip 1 / 2", "Offense", "3rd & 7, OWN 25", "game2"]
+        );
+        assert_eq!(texts[0].caption, "1/2 | Offense | 3rd & 7, OWN 25 | game2");
+        assert_eq!(texts[1].caption, "2/2 | game1");
+        let files = project.reel_clip_files(&reel).unwrap();
+        assert!(files[0].ends_with("game2.mp4_00000851Off.mp4"));
+        assert!(files[1].ends_with("game1.mp4_00003601.mp4"));
+        assert_eq!(project.reel_clips().count(), 2);
+
+        reel.clips.push(ReelClip {
+            media: "game1.mp4".to_owned(),
+            start: 1,
+        });
+        assert!(project.reel_clip_files(&reel).is_err());
+
+        project.reels_mut().push(reel.clone());
+        project.save().unwrap();
+        assert_eq!(Project::load(&project_dir).unwrap().reels(), &[reel]);
+    }
+
+    #[test]
+    fn test_recover_clips() {
+        let project_dir = Path::new("tests")
+            .join("output")
+            .join("project_recover_clips");
+        let _ = std::fs::remove_dir_all(&project_dir);
+        let clips_dir = project_dir.join(CLIPS_DIR_NAME);
+        std::fs::create_dir_all(&clips_dir).unwrap();
+        for file_name in [
+            "game.mp4_00003601Off.mp4",
+            "game.mp4_00009602.mp4",
+            "game.mp4_00016101ST.mp4",
+            "highlights.mp4",
+        ]
+        .iter()
+        {
+            std::fs::File::create(clips_dir.join(file_name)).unwrap();
+        }
+
+        let mut project = Project::load(&project_dir).unwrap();
+        let media_path = project_dir.join("game.mp4");
+        let annotation = PlayAnnotation {
+            note: "kept".to_owned(),
+            ..Default::default()
+        };
+        project
+            .media_metadata_mut(&media_path)
+            .unwrap()
+            .clips
+            .insert(
+                9602,
+                Clip {
+                    annotation: annotation.clone(),
+                    ..Default::default()
+                },
+            );
+
+        let (changed, problems) = project.recover_clips(&[], |_| Ok(4000));
+        assert_eq!(changed, 2);
+        assert_eq!(problems.len(), 2);
+
+        let clips = &project.media_metadata(&media_path).unwrap().clips;
+        assert_eq!(clips.keys().copied().collect::<Vec<_>>(), vec![3601, 9602]);
+        assert_eq!(clips[&3601].end, Some(7601));
+        assert_eq!(clips[&3601].tags, vec!["Offense".to_owned()]);
+        assert_eq!(clips[&9602].end, Some(13602));
+        assert_eq!(clips[&9602].annotation, annotation);
+
+        assert_eq!(project.recover_clips(&[], |_| Ok(4000)).0, 0);
+    }
+
+    #[test]
+    fn test_angles() {
+        let project_dir = Path::new("tests").join("output").join("project_angles");
+        let _ = std::fs::remove_dir_all(&project_dir);
+        std::fs::create_dir_all(project_dir.join(PROJECT_DIR_NAME)).unwrap();
+        let mut project_json = serde_json::json!({
+            "version": PROJECT_FILE_VERSION,
+            "media": {},
+            "angles": [{
+                "name": "game1",
+                "angles": [
+                    { "media": "game1_sideline.mp4" },
+                    { "media": "game1_endzone.mp4", "offset_ms": 2500 },
+                    { "media": "game1_drone.mp4", "offset_ms": -1000 }
+                ]
+            }]
+        });
+        let project_file_path = Project::file_path(&project_dir);
+        std::fs::write(&project_file_path, project_json.to_string()).unwrap();
+
+        let project = Project::load(&project_dir).unwrap();
+        let sideline = project_dir.join("game1_sideline.mp4");
+        let endzone = project_dir.join("game1_endzone.mp4");
+        let drone = project_dir.join("game1_drone.mp4");
+        assert!(!project.is_following_angle(&sideline));
+        assert!(project.is_following_angle(&endzone));
+        assert!(!project.is_following_angle(&project_dir.join("game2.mp4")));
+        assert_eq!(
+            project.other_angles(&sideline),
+            vec![(endzone.clone(), 2500), (drone.clone(), -1000)]
+        );
+        assert_eq!(
+            project.other_angles(&endzone),
+            vec![(sideline, -2500), (drone, -3500)]
+        );
+        assert!(project
+            .other_angles(&project_dir.join("game2.mp4"))
+            .is_empty());
+
+        project_json["angles"][0]["angles"][2]["media"] = "game1_endzone.mp4".into();
+        std::fs::write(&project_file_path, project_json.to_string()).unwrap();
+        assert!(Project::load(&project_dir).is_err());
+    }
+
+    #[test]
+    fn test_review_clips() {
+        let project: Project = serde_json::from_value(serde_json::json!({
+            "version": PROJECT_FILE_VERSION,
+            "media": {
+                "game2.mp4": { "clips": {
+                    "5000": { "end": 9000, "tags": ["Offense"] }
+                }, "cutmarks": null },
+                "game1_sideline.mp4": { "clips": {
+                    "20000": { "end": 26000, "tags": ["Offense", "RedZone"] },
+                    "3000": { "end": 8000, "tags": ["Offense"] },
+                    "12000": { "end": 15000, "tags": ["Defense"] },
+                    "30000": { "end": null, "tags": ["Offense"] }
+                }, "cutmarks": null },
+                "game1_endzone.mp4": { "clips": {
+                    "5500": { "end": 10500, "tags": ["Offense"] }
+                }, "cutmarks": null }
+            },
+            "angles": [{
+                "name": "game1",
+                "angles": [
+                    { "media": "game1_sideline.mp4" },
+                    { "media": "game1_endzone.mp4", "offset_ms": 2500 }
+                ]
+            }]
+        }))
+        .unwrap();
+
+        let offense = project.review_clips(&["Offense".to_owned()]);
+        let starts: Vec<(PathBuf, i64, i64)> = offense
+            .into_iter()
+            .map(|clip| (clip.media_path, clip.start, clip.end))
+            .collect();
+        assert_eq!(
+            starts,
+            vec![
+                (PathBuf::from("game1_sideline.mp4"), 3000, 8000),
+                (PathBuf::from("game1_sideline.mp4"), 20000, 26000),
+                (PathBuf::from("game2.mp4"), 5000, 9000),
+            ]
+        );
+        assert_eq!(
+            project
+                .review_clips(&["Offense".to_owned(), "RedZone".to_owned()])
+                .len(),
+            1
+        );
+        assert_eq!(project.review_clips(&[]).len(), 4);
+    }
+}
@@ -0,0 +1,236 @@
+//! Settings of VAC that are the same for all projects, read from `settings.toml`
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+const SETTINGS_FILE_NAME: &str = "settings.toml";
+
+/// Locations of the external tools. Tools without a location are searched next to the
+/// executable and in the `PATH`.
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct ToolSettings {
+    pub(crate) ffmpeg: Option<PathBuf>,
+    pub(crate) ffprobe: Option<PathBuf>,
+}
+
+/// Speeds of the video
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct PlaybackSettings {
+    /// Rates `IncreaseSpeed` and `DecreaseSpeed` step through, slowest first
+    pub(crate) speeds: Vec<f32>,
+}
+
+impl Default for PlaybackSettings {
+    fn default() -> Self {
+        PlaybackSettings {
+            speeds: vec![0.1, 0.25, 0.5, 0.75, 1.0, 1.5, 2.0, 4.0],
+        }
+    }
+}
+
+/// Rates closer than this are the same speed, VLC does not return exactly the rate it was given
+const SPEED_TOLERANCE: f32 = 0.01;
+
+impl PlaybackSettings {
+    /// The next speed that is faster than `rate`, or the fastest one
+    pub(crate) fn faster(&self, rate: f32) -> f32 {
+        self.speeds
+            .iter()
+            .copied()
+            .find(|speed| *speed > rate + SPEED_TOLERANCE)
+            .unwrap_or_else(|| *self.speeds.last().unwrap())
+    }
+
+    /// The next speed that is slower than `rate`, or the slowest one
+    pub(crate) fn slower(&self, rate: f32) -> f32 {
+        self.speeds
+            .iter()
+            .copied()
+            .rev()
+            .find(|speed| *speed < rate - SPEED_TOLERANCE)
+            .unwrap_or(self.speeds[0])
+    }
+}
+
+/// Response of the analog controls of gamepads
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct GamepadSettings {
+    /// Points `[pressure, speed]` of the shuttle speed over the pressure of a trigger, by
+    /// increasing pressure. The speed is interpolated between the points. Below the first point
+    /// the trigger is released.
+    pub(crate) shuttle_curve: Vec<[f32; 2]>,
+}
+
+impl Default for GamepadSettings {
+    fn default() -> Self {
+        GamepadSettings {
+            shuttle_curve: vec![[0.1, 0.25], [0.5, 1.0], [0.8, 4.0], [1.0, 8.0]],
+        }
+    }
+}
+
+impl GamepadSettings {
+    /// Speed of scrubbing, in multiples of the normal speed, when a trigger is pressed by
+    /// `pressure` between 0 and 1
+    pub(crate) fn shuttle_speed(&self, pressure: f32) -> f32 {
+        let mut previous: Option<[f32; 2]> = None;
+        for point in self.shuttle_curve.iter().copied() {
+            let [point_pressure, point_speed] = point;
+            if pressure < point_pressure {
+                return match previous {
+                    Some([previous_pressure, previous_speed]) => {
+                        let share =
+                            (pressure - previous_pressure) / (point_pressure - previous_pressure);
+                        previous_speed + share * (point_speed - previous_speed)
+                    }
+                    None => 0.0,
+                };
+            }
+            previous = Some(point);
+        }
+        previous.map(|[_, speed]| speed).unwrap_or(0.0)
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Settings {
+    pub(crate) tools: ToolSettings,
+    pub(crate) playback: PlaybackSettings,
+    pub(crate) gamepad: GamepadSettings,
+}
+
+impl Settings {
+    /// Loads settings.toml from next to the executable or from the user's config directory.
+    /// Falls back to the defaults if there is no settings file or it cannot be read.
+    pub(crate) fn load() -> Settings {
+        let path = match crate::input::find_config_file(SETTINGS_FILE_NAME) {
+            Some(path) => path,
+            None => return Settings::default(),
+        };
+        match Settings::load_file(&path) {
+            Ok(settings) => {
+                println!("loaded settings from {:?}", path);
+                settings
+            }
+            Err(e) => {
+                println!("{:?}: {}. Using default settings", path, e);
+                Settings::default()
+            }
+        }
+    }
+
+    fn load_file(path: &Path) -> Result<Settings, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let mut settings: Settings = toml::from_str(&content).map_err(|e| e.to_string())?;
+        let speeds = &mut settings.playback.speeds;
+        if speeds.is_empty() || speeds.iter().any(|speed| speed.is_nan() || *speed <= 0.0) {
+            return Err("playback speeds must be a list of rates greater than 0".to_owned());
+        }
+        speeds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let curve = &mut settings.gamepad.shuttle_curve;
+        if curve.iter().any(|[pressure, speed]| {
+            pressure.is_nan()
+                || *pressure < 0.0
+                || *pressure > 1.0
+                || speed.is_nan()
+                || *speed < 0.0
+        }) {
+            return Err(
+                "the shuttle curve must be a list of [pressure, speed] with pressures between 0 and 1"
+                    .to_owned(),
+            );
+        }
+        curve.sort_by(|a, b| a[0].partial_cmp(&b[0]).unwrap());
+        // relative paths are relative to the settings file, e.g. a bundled ffmpeg
+        if let Some(dir) = path.parent() {
+            for tool in [&mut settings.tools.ffmpeg, &mut settings.tools.ffprobe].iter_mut() {
+                if let Some(tool_path) = tool.as_mut() {
+                    *tool_path = dir.join(&tool_path);
+                }
+            }
+        }
+        Ok(settings)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_shipped_settings_are_defaults() {
+        let settings: Settings = toml::from_str(include_str!("../settings.toml")).unwrap();
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test]
+    fn test_load_settings() {
+        let dir = Path::new("tests").join("output").join("settings");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(SETTINGS_FILE_NAME);
+        std::fs::write(&path, "[tools]\nffmpeg = \"bin/ffmpeg.exe\"\n").unwrap();
+        let settings = Settings::load_file(&path).unwrap();
+        assert_eq!(
+            settings.tools.ffmpeg,
+            Some(dir.join("bin").join("ffmpeg.exe"))
+        );
+        assert_eq!(settings.tools.ffprobe, None);
+
+        std::fs::write(&path, "[tools]\nfmpeg = \"ffmpeg.exe\"\n").unwrap();
+        assert!(Settings::load_file(&path).is_err());
+
+        std::fs::write(&path, "[playback]\nspeeds = [2.0, 0.5, 1.0]\n").unwrap();
+        let settings = Settings::load_file(&path).unwrap();
+        assert_eq!(settings.playback.speeds, vec![0.5, 1.0, 2.0]);
+        std::fs::write(&path, "[playback]\nspeeds = [1.0, 0.0]\n").unwrap();
+        assert!(Settings::load_file(&path).is_err());
+
+        std::fs::write(
+            &path,
+            "[gamepad]\nshuttle_curve = [[1.0, 4.0], [0.2, 1.0]]\n",
+        )
+        .unwrap();
+        let settings = Settings::load_file(&path).unwrap();
+        assert_eq!(settings.gamepad.shuttle_curve, vec![[0.2, 1.0], [1.0, 4.0]]);
+        std::fs::write(&path, "[gamepad]\nshuttle_curve = [[1.5, 4.0]]\n").unwrap();
+        assert!(Settings::load_file(&path).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_speed_ladder() {
+        let playback = PlaybackSettings::default();
+        assert_eq!(playback.faster(1.0), 1.5);
+        assert_eq!(playback.faster(0.9), 1.0);
+        // VLC returns rates that are slightly off
+        assert_eq!(playback.faster(0.2499), 0.5);
+        assert_eq!(playback.faster(4.0), 4.0);
+        assert_eq!(playback.slower(1.0), 0.75);
+        assert_eq!(playback.slower(1.2), 1.0);
+        assert_eq!(playback.slower(0.1), 0.1);
+        assert_eq!(playback.slower(0.05), 0.1);
+    }
+
+    #[test]
+    fn test_shuttle_curve() {
+        let gamepad = GamepadSettings::default();
+        assert_eq!(gamepad.shuttle_speed(0.0), 0.0);
+        assert_eq!(gamepad.shuttle_speed(0.09), 0.0);
+        assert_eq!(gamepad.shuttle_speed(0.1), 0.25);
+        assert!((gamepad.shuttle_speed(0.3) - 0.625).abs() < 0.001);
+        assert_eq!(gamepad.shuttle_speed(0.8), 4.0);
+        assert_eq!(gamepad.shuttle_speed(1.0), 8.0);
+
+        let empty = GamepadSettings {
+            shuttle_curve: Vec::new(),
+        };
+        assert_eq!(empty.shuttle_speed(1.0), 0.0);
+    }
+}